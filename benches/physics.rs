@@ -0,0 +1,66 @@
+//! Physics throughput benchmarks. Run with `cargo bench --bench physics`.
+
+use std::{hint::black_box, time::Instant};
+
+use bouncy::{game::Game, level::Level, shape::Shape};
+use egui::Pos2;
+
+const WARMUP_ITERATIONS: u32 = 1_000;
+const ITERATIONS: u32 = 100_000;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    for _ in 0..WARMUP_ITERATIONS {
+        f();
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iteration = start.elapsed() / ITERATIONS;
+
+    println!("{name:<32} {:>8} ns/iter", per_iteration.as_nanos());
+}
+
+fn game(num_sides: usize) -> Game {
+    Game::new(Level::simple_polygon(num_sides), 1024.)
+}
+
+fn bench_tick(name: &str, mut game: Game) {
+    bench(name, || {
+        game.advance_to(game.tick_counter + 1);
+        black_box(&game);
+    });
+}
+
+fn main() {
+    bench_tick("tick/hexagon", game(6));
+    bench_tick("tick/32-gon", game(32));
+
+    let mut touching = game(6);
+    let side_normal = egui::Vec2::angled(std::f32::consts::FRAC_PI_6);
+    let ball = &mut touching.level.balls[0];
+    ball.center = Pos2::ZERO + side_normal * (0.866 - ball.radius);
+    let ball = ball.clone();
+    bench_tick("tick/hexagon_ball_at_wall", touching.clone());
+
+    bench("detect_collisions/hexagon", || {
+        black_box(touching.detect_collisions(black_box(&ball)));
+    });
+
+    // Checks every wall, to see what parallelising the per-segment work could save
+    let mut large = game(64);
+    let ball = &mut large.level.balls[0];
+    ball.center = Pos2::new(1.0 - ball.radius, 0.0);
+    let ball = ball.clone();
+    bench("detect_collisions/64-gon_at_wall", || {
+        black_box(large.detect_collisions(black_box(&ball)));
+    });
+
+    for num_sides in [6, 64] {
+        let shape = Shape::regular_polygon(num_sides, 1.0, Pos2::ZERO);
+        bench(&format!("shape_rotate/{num_sides}-gon"), || {
+            black_box(shape.rotate(black_box(0.1), Pos2::ZERO));
+        });
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+
+use crate::{game::Game, level::Level};
+
+const SPEED_DEMON_SECS: f32 = 10.0;
+const ECONOMIST_WORK_FRACTION: f32 = 0.8;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Achievement {
+    FirstEscape,
+    /// Escaped the level with the given id name in under ten seconds
+    SpeedDemon(String),
+    /// Escaped the level with the given id name with most of the work budget left
+    Economist(String),
+    /// Escaped having braked without ever reversing the spin
+    PerfectBrake,
+    /// Escaped without touching the walls
+    Untouchable,
+    Completionist,
+}
+
+impl Achievement {
+    /// Every achievement that can be unlocked with these levels
+    pub fn all(levels: &[Level]) -> Vec<Achievement> {
+        let mut all = vec![Achievement::FirstEscape];
+        for level in levels {
+            all.push(Achievement::SpeedDemon(level.id_name.clone()));
+            all.push(Achievement::Economist(level.id_name.clone()));
+        }
+        all.extend([
+            Achievement::PerfectBrake,
+            Achievement::Untouchable,
+            Achievement::Completionist,
+        ]);
+        all
+    }
+
+    /// Key used to store the achievement once unlocked
+    pub fn key(&self) -> String {
+        match self {
+            Achievement::FirstEscape => "first_escape".to_owned(),
+            Achievement::SpeedDemon(id_name) => format!("speed_demon_{id_name}"),
+            Achievement::Economist(id_name) => format!("economist_{id_name}"),
+            Achievement::PerfectBrake => "perfect_brake".to_owned(),
+            Achievement::Untouchable => "untouchable".to_owned(),
+            Achievement::Completionist => "completionist".to_owned(),
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Achievement::FirstEscape => "First Escape",
+            Achievement::SpeedDemon(_) => "Speed Demon",
+            Achievement::Economist(_) => "Economist",
+            Achievement::PerfectBrake => "Perfect Brake",
+            Achievement::Untouchable => "Untouchable",
+            Achievement::Completionist => "Completionist",
+        }
+    }
+
+    pub fn description(&self, levels: &[Level]) -> String {
+        let level_name = |id_name: &str| {
+            levels
+                .iter()
+                .find(|level| level.id_name == id_name)
+                .map_or(id_name.to_owned(), |level| level.name.clone())
+        };
+
+        match self {
+            Achievement::FirstEscape => "Escape any level".to_owned(),
+            Achievement::SpeedDemon(id_name) => format!(
+                "Escape {} in under {SPEED_DEMON_SECS:.0} s",
+                level_name(id_name)
+            ),
+            Achievement::Economist(id_name) => format!(
+                "Escape {} with over {:.0} % of the work budget left",
+                level_name(id_name),
+                ECONOMIST_WORK_FRACTION * 100.
+            ),
+            Achievement::PerfectBrake => {
+                "Escape using the brake without reversing the spin".to_owned()
+            }
+            Achievement::Untouchable => "Escape without hitting a wall".to_owned(),
+            Achievement::Completionist => "Escape every level".to_owned(),
+        }
+    }
+
+    /// Achievements earned by escaping in `game`, given the levels completed so far including it
+    pub fn earned(game: &Game, completed_levels: &HashSet<String>, levels: &[Level]) -> Vec<Self> {
+        let id_name = &game.level.id_name;
+        let mut earned = vec![Achievement::FirstEscape];
+
+        if game.scaled_elapsed.as_secs_f32() < SPEED_DEMON_SECS {
+            earned.push(Achievement::SpeedDemon(id_name.clone()));
+        }
+
+        // Practice runs have an unlimited budget
+        let max_work = game.level.max_brake_work + game.level.max_boost_work;
+        if !game.speed_run_mode
+            && max_work > 0.0
+            && game.work_remaining() / max_work > ECONOMIST_WORK_FRACTION
+        {
+            earned.push(Achievement::Economist(id_name.clone()));
+        }
+
+        if game.stats.work_spent_brake > 0.0 && game.stats.brake_reversals == 0 {
+            earned.push(Achievement::PerfectBrake);
+        }
+
+        if game.stats.total_collisions == 0 {
+            earned.push(Achievement::Untouchable);
+        }
+
+        if levels
+            .iter()
+            .all(|level| completed_levels.contains(&level.id_name))
+        {
+            earned.push(Achievement::Completionist);
+        }
+
+        earned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_earned() {
+        let levels = vec![Level::simple_polygon(5), Level::simple_polygon(6)];
+        let mut game = Game::new(levels[1].clone(), 1024.);
+        game.scaled_elapsed = web_time::Duration::from_secs(20);
+        game.stats.total_collisions = 3;
+        game.stats.work_spent_brake = 1.0;
+        game.stats.brake_reversals = 2;
+        game.input_work.boost = game.level.max_boost_work;
+
+        let completed = HashSet::from([levels[1].id_name.clone()]);
+        assert_eq!(
+            Achievement::earned(&game, &completed, &levels),
+            vec![Achievement::FirstEscape]
+        );
+
+        game.scaled_elapsed = web_time::Duration::from_secs(5);
+        game.stats.total_collisions = 0;
+        game.stats.brake_reversals = 0;
+        game.input_work.boost = 0.0;
+        let completed = levels.iter().map(|level| level.id_name.clone()).collect();
+        let earned = Achievement::earned(&game, &completed, &levels);
+        let id_name = levels[1].id_name.clone();
+        assert_eq!(
+            earned,
+            vec![
+                Achievement::FirstEscape,
+                Achievement::SpeedDemon(id_name.clone()),
+                Achievement::Economist(id_name),
+                Achievement::PerfectBrake,
+                Achievement::Untouchable,
+                Achievement::Completionist,
+            ]
+        );
+
+        let keys: HashSet<String> = Achievement::all(&levels)
+            .iter()
+            .map(Achievement::key)
+            .collect();
+        assert_eq!(keys.len(), 4 + 2 * levels.len());
+    }
+}
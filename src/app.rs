@@ -1,67 +1,417 @@
+use std::collections::{HashMap, HashSet};
+
 use egui::{emath::TSTransform, Color32, RichText};
 use ringbuffer::RingBuffer;
 
 use crate::{
+    achievement::Achievement,
+    control::{Input, InputSet},
     drawable::Drawable,
-    game::{self, Game},
-    level::Level,
+    error::GameError,
+    game::{self, Game, InputLog, RunStats},
+    level::{Level, LevelBundle},
+    metrics::AppMetrics,
+    run_history::{self, Outcome, RunRecord},
+    settings::{AppSettings, KeyAction, KeyBindings},
+    ui::{
+        particles::{self, Particle},
+        timer::TimerRing,
+    },
+    util,
 };
 
+const SPEED_WARNING_THRESHOLD: f32 = 5.0;
+
+const INTRO_DURATION: web_time::Duration = web_time::Duration::from_millis(500);
+const COUNTDOWN_DURATION: web_time::Duration = web_time::Duration::from_millis(3500);
+
+const HISTOGRAM_BUCKETS: usize = 20;
+const HISTOGRAM_BUCKET_MS: f32 = 5.0;
+
+/// How long the browser gets to start a screenshot download before its URL is revoked
+#[cfg(target_arch = "wasm32")]
+const URL_REVOKE_DELAY_MS: i32 = 10_000;
+
+const TOAST_DURATION: web_time::Duration = web_time::Duration::from_secs(3);
+
 #[derive(Debug)]
 pub enum State {
     Menu,
+    Achievements,
+    Stats,
+    LevelIntro {
+        game: Game,
+        intro_start: web_time::Instant,
+    },
+    StartingGame {
+        game: Game,
+        countdown_start: web_time::Instant,
+    },
     Playing(Game),
+    Paused(Game),
     Victory(Game),
     Defeat(Game),
+    Replay {
+        game: Game,
+        original: Box<Game>,
+    },
+    /// Something went wrong that ended the run, with a message for the player
+    Error(String),
+}
+
+impl State {
+    fn name(&self) -> &'static str {
+        match self {
+            State::Menu => "Menu",
+            State::Achievements => "Achievements",
+            State::Stats => "Stats",
+            State::LevelIntro { .. } => "LevelIntro",
+            State::StartingGame { .. } => "StartingGame",
+            State::Playing(_) => "Playing",
+            State::Paused(_) => "Paused",
+            State::Victory(_) => "Victory",
+            State::Defeat(_) => "Defeat",
+            State::Replay { .. } => "Replay",
+            State::Error(_) => "Error",
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct App {
-    target_frame_rate: f32,
+    settings: AppSettings,
+    tick_rate: f32,
     previous_frame_times: ringbuffer::AllocRingBuffer<web_time::Instant>,
     state: State,
+    /// Name of the level bundle, shown as the title of the game
+    title: String,
     levels: Vec<Level>,
-    current_level: uuid::Uuid,
+    current_level: String,
+    best_ratings: HashMap<String, u8>,
+    best_scores: HashMap<String, u32>,
+    speedrun_times: HashMap<String, f32>,
+    completed_levels: HashSet<String>,
+    best_times: HashMap<String, f32>,
+    /// Inputs of the latest winning run of each level, replayed as a ghost
+    ghost_recordings: HashMap<String, InputLog>,
+    ghost_game: Option<Game>,
+    show_ghost: bool,
+    show_settings: bool,
+    victory_particles: Vec<Particle>,
+    last_particle_update: web_time::Instant,
+    show_debug_window: bool,
+    /// Action waiting for its new key while rebinding in the settings window
+    listening_for: Option<KeyAction>,
+    /// Keys of the unlocked achievements
+    achievements: HashSet<String>,
+    /// Newly unlocked achievements, announced until their toast expires
+    achievement_toasts: Vec<(Achievement, web_time::Instant)>,
+    /// Play time and number of sessions before this one
+    total_playtime_secs: f64,
+    session_count: u32,
+    session_start: web_time::Instant,
+    /// Counters carried over from earlier sessions and added to in this one
+    metrics: AppMetrics,
+    /// Recent runs of each level, most recent first
+    run_histories: HashMap<String, Vec<RunRecord>>,
+    show_run_history: bool,
+    /// Result of the last optimal escape search, with the tick it was run from. The search is
+    /// too slow to run every frame, so it only runs when asked for from the debug window.
+    escape_estimate: Option<(u64, Option<f32>)>,
 }
 
 impl App {
-    pub fn new(
-        cc: &eframe::CreationContext<'_>,
-        target_frame_rate: f32,
-        levels: Vec<Level>,
-    ) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, bundle: LevelBundle) -> Self {
+        let app = Self::from_storage(cc.storage, bundle);
+        if app.settings.fullscreen {
+            cc.egui_ctx
+                .send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        }
+        app
+    }
+
+    /// Sets up the app from whatever was saved in earlier sessions
+    fn from_storage(storage: Option<&dyn eframe::Storage>, bundle: LevelBundle) -> Self {
+        let LevelBundle {
+            name: title,
+            levels,
+            ..
+        } = bundle;
         if levels.is_empty() {
             panic!("No levels provided");
         }
 
-        let current_level_from_storage: Option<uuid::Uuid> = if let Some(storage) = cc.storage {
-            eframe::get_value::<Option<uuid::Uuid>>(storage, "current_level").unwrap_or_default()
+        let current_level_from_storage: Option<String> = if let Some(storage) = storage {
+            eframe::get_value::<Option<String>>(storage, "current_level").unwrap_or_default()
         } else {
             None
         };
 
-        let current_level = if current_level_from_storage.is_none()
-            || !levels
-                .iter()
-                .any(|level| level.id == current_level_from_storage.unwrap())
-        {
-            levels[0].id
-        } else {
-            current_level_from_storage.unwrap()
-        };
+        let settings = storage
+            .and_then(|storage| eframe::get_value::<AppSettings>(storage, "settings"))
+            .unwrap_or_else(|| Self::load_legacy_settings(storage));
+
+        let best_ratings = Self::load_per_level(storage, &levels, "rating");
+        let best_scores = Self::load_per_level(storage, &levels, "score");
+        let speedrun_times = Self::load_per_level(storage, &levels, "speedrun_time");
+        let best_times = Self::load_per_level(storage, &levels, "best_time");
+        let completed_levels = storage
+            .and_then(|storage| eframe::get_value::<HashSet<String>>(storage, "completed_levels"))
+            .unwrap_or_default();
+        let achievements = storage
+            .and_then(|storage| eframe::get_value::<HashSet<String>>(storage, "achievements"))
+            .unwrap_or_default();
+        let total_playtime_secs = storage
+            .and_then(|storage| eframe::get_value::<f64>(storage, "total_playtime_secs"))
+            .unwrap_or_default();
+        let session_count = storage
+            .and_then(|storage| eframe::get_value::<u32>(storage, "session_count"))
+            .unwrap_or_default();
+        let metrics = storage
+            .and_then(|storage| eframe::get_value::<AppMetrics>(storage, "metrics"))
+            .unwrap_or_default();
+        let run_histories = Self::load_run_histories(storage, &levels);
+
+        let current_level = current_level_from_storage
+            .filter(|id_name| levels.iter().any(|level| level.id_name == *id_name))
+            .unwrap_or_else(|| levels[0].id_name.clone());
 
         Self {
-            target_frame_rate,
+            settings,
+            tick_rate: 1024.,
             previous_frame_times: ringbuffer::AllocRingBuffer::new(128),
             state: State::Menu,
+            title,
             levels,
             current_level,
+            best_ratings,
+            best_scores,
+            speedrun_times,
+            completed_levels,
+            best_times,
+            ghost_recordings: HashMap::new(),
+            ghost_game: None,
+            show_ghost: true,
+            show_settings: false,
+            victory_particles: Vec::new(),
+            last_particle_update: web_time::Instant::now(),
+            show_debug_window: false,
+            listening_for: None,
+            achievements,
+            achievement_toasts: Vec::new(),
+            total_playtime_secs,
+            session_count,
+            session_start: web_time::Instant::now(),
+            metrics,
+            run_histories,
+            show_run_history: false,
+            escape_estimate: None,
+        }
+    }
+
+    /// Lifetime performance counters, including this session
+    pub fn collect_metrics(&self) -> AppMetrics {
+        AppMetrics {
+            sessions: self.session_count + 1,
+            ..self.metrics
         }
     }
 
+    /// Settings saved individually, before they were gathered into `AppSettings`
+    fn load_legacy_settings(storage: Option<&dyn eframe::Storage>) -> AppSettings {
+        let defaults = AppSettings::default();
+        let Some(storage) = storage else {
+            return defaults;
+        };
+
+        AppSettings {
+            target_fps: eframe::get_value(storage, "target_frame_rate")
+                .unwrap_or(defaults.target_fps),
+            fullscreen: eframe::get_value(storage, "fullscreen").unwrap_or(defaults.fullscreen),
+            show_ball_trail: eframe::get_value(storage, "show_trail")
+                .unwrap_or(defaults.show_ball_trail),
+            key_bindings: eframe::get_value(storage, "key_bindings")
+                .unwrap_or(defaults.key_bindings),
+            ..defaults
+        }
+    }
+
+    /// Loads values stored under `format!("{prefix}_{id_name}")` for each level
+    fn load_per_level<T: serde::de::DeserializeOwned>(
+        storage: Option<&dyn eframe::Storage>,
+        levels: &[Level],
+        prefix: &str,
+    ) -> HashMap<String, T> {
+        let Some(storage) = storage else {
+            return HashMap::new();
+        };
+
+        levels
+            .iter()
+            .filter_map(|level| {
+                let key = format!("{}_{}", prefix, level.id_name);
+                eframe::get_value::<T>(storage, &key).map(|value| (level.id_name.clone(), value))
+            })
+            .collect()
+    }
+
+    /// Run histories are stored as JSON under `format!("history_{id_name}")`
+    fn load_run_histories(
+        storage: Option<&dyn eframe::Storage>,
+        levels: &[Level],
+    ) -> HashMap<String, Vec<RunRecord>> {
+        let Some(storage) = storage else {
+            return HashMap::new();
+        };
+
+        levels
+            .iter()
+            .filter_map(|level| {
+                let json = storage.get_string(&format!("history_{}", level.id_name))?;
+                let history = serde_json::from_str(&json).ok()?;
+                Some((level.id_name.clone(), history))
+            })
+            .collect()
+    }
+
+    fn record_run(&mut self, game: &Game, outcome: Outcome) {
+        let timestamp = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let history = self
+            .run_histories
+            .entry(game.level.id_name.clone())
+            .or_default();
+        run_history::push_run(history, RunRecord::new(game, outcome, timestamp));
+    }
+
+    fn draw_run_history(&mut self, ctx: &egui::Context) {
+        let (State::Victory(game) | State::Defeat(game)) = &self.state else {
+            // Start closed on the next results screen rather than reopening with it
+            self.show_run_history = false;
+            return;
+        };
+        let history = self
+            .run_histories
+            .get(&game.level.id_name)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        // Rewinds and respawns shrink the budget during a run, so measure against the original
+        let work_budget = self
+            .levels
+            .iter()
+            .find(|level| level.id_name == game.level.id_name)
+            .map_or(0.0, |level| level.max_brake_work + level.max_boost_work);
+
+        egui::Window::new("History")
+            .open(&mut self.show_run_history)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if history.is_empty() {
+                    ui.label("No runs yet");
+                    return;
+                }
+
+                egui::Grid::new("run_history").striped(true).show(ui, |ui| {
+                    ui.strong("");
+                    ui.strong("Time");
+                    ui.strong("Work");
+                    ui.strong("Collisions");
+                    ui.end_row();
+
+                    for run in history {
+                        ui.label(run.outcome.emoji());
+                        ui.label(format!("{:.1} s", run.elapsed_secs));
+                        if work_budget > 0.0 {
+                            ui.label(format!("{:.0} %", 100. * run.work_spent / work_budget));
+                        } else {
+                            ui.label("-");
+                        }
+                        ui.label(run.collisions.to_string());
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    fn record_victory(&mut self, game: &Game) {
+        if !game.speed_run_mode {
+            self.ghost_recordings
+                .insert(game.level.id_name.clone(), game.input_log.clone());
+        }
+
+        self.completed_levels.insert(game.level.id_name.clone());
+
+        // Practice runs ignore the work budget, so they are tracked separately
+        if game.speed_run_mode {
+            let time = game.scaled_elapsed.as_secs_f32();
+            let best_time = self
+                .speedrun_times
+                .entry(game.level.id_name.clone())
+                .or_insert(time);
+            *best_time = best_time.min(time);
+            return;
+        }
+
+        let rating = game.rating();
+        let best = self
+            .best_ratings
+            .entry(game.level.id_name.clone())
+            .or_default();
+        *best = (*best).max(rating);
+
+        let best_score = self
+            .best_scores
+            .entry(game.level.id_name.clone())
+            .or_default();
+        *best_score = (*best_score).max(game.score);
+
+        let time = game.scaled_elapsed.as_secs_f32();
+        let best_time = self
+            .best_times
+            .entry(game.level.id_name.clone())
+            .or_insert(time);
+        *best_time = best_time.min(time);
+    }
+
+    fn unlock_achievements(&mut self, game: &Game) {
+        for achievement in Achievement::earned(game, &self.completed_levels, &self.levels) {
+            if self.achievements.insert(achievement.key()) {
+                self.achievement_toasts
+                    .push((achievement, web_time::Instant::now()));
+            }
+        }
+    }
+
+    fn draw_achievement_toasts(&mut self, ctx: &egui::Context) {
+        self.achievement_toasts
+            .retain(|(_, unlocked)| unlocked.elapsed() < TOAST_DURATION);
+
+        for (idx, (achievement, unlocked)) in self.achievement_toasts.iter().enumerate() {
+            egui::Window::new("🏆 Achievement unlocked")
+                .id(egui::Id::new(("achievement_toast", idx)))
+                .anchor(
+                    egui::Align2::RIGHT_TOP,
+                    egui::vec2(-8.0, 48.0 + 72.0 * idx as f32),
+                )
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(RichText::new(achievement.title()).strong());
+                    ui.label(achievement.description(&self.levels));
+                });
+            ctx.request_repaint_after(TOAST_DURATION.saturating_sub(unlocked.elapsed()));
+        }
+    }
+
+    fn repaint_interval(target_frame_rate: f32) -> web_time::Duration {
+        web_time::Duration::from_secs_f32(1.0 / target_frame_rate)
+    }
+
     fn compute_fps(&self) -> f32 {
         if self.previous_frame_times.len() < 2 {
-            return self.target_frame_rate;
+            return self.settings.target_fps;
         }
 
         let first = self.previous_frame_times.front().unwrap();
@@ -76,15 +426,112 @@ impl App {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 egui::widgets::global_theme_preference_buttons(ui);
+                if ui.button("⚙ Settings").clicked() {
+                    self.show_settings = !self.show_settings;
+                }
+
+                let fullscreen_label = if self.settings.fullscreen {
+                    "⛶ Exit Fullscreen"
+                } else {
+                    "⛶ Fullscreen"
+                };
+                if ui.button(fullscreen_label).clicked() {
+                    self.settings.fullscreen = !self.settings.fullscreen;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(
+                        self.settings.fullscreen,
+                    ));
+                }
+
+                if ui
+                    .toggle_value(&mut self.settings.debug_mode, "🐛 Debug")
+                    .changed()
+                {
+                    self.show_debug_window = self.settings.debug_mode;
+                }
             });
         });
 
+        egui::Window::new("Settings")
+            .open(&mut self.show_settings)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::Slider::new(&mut self.settings.target_fps, 15.0..=144.0)
+                        .step_by(1.0)
+                        .text("Frame rate (Hz)"),
+                );
+                ui.checkbox(&mut self.settings.show_ball_trail, "Show ball trail");
+                ui.checkbox(&mut self.settings.collision_sparkles, "Collision sparkles");
+                if ui
+                    .checkbox(&mut self.settings.fullscreen, "Fullscreen")
+                    .changed()
+                {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(
+                        self.settings.fullscreen,
+                    ));
+                }
+                if ui
+                    .checkbox(&mut self.settings.debug_mode, "Debug mode")
+                    .changed()
+                {
+                    self.show_debug_window = self.settings.debug_mode;
+                }
+
+                ui.separator();
+                ui.label(RichText::new("Key Bindings").strong());
+                egui::Grid::new("key_bindings").show(ui, |ui| {
+                    for action in KeyAction::ALL {
+                        ui.label(action.label());
+                        let label = if self.listening_for == Some(action) {
+                            "Press a key…"
+                        } else {
+                            self.settings.key_bindings.key(action).name()
+                        };
+                        if ui.button(label).clicked() {
+                            self.listening_for = Some(action);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                if let Some(action) = self.listening_for {
+                    let pressed = ui.input(|i| {
+                        i.events.iter().find_map(|event| match event {
+                            egui::Event::Key {
+                                key, pressed: true, ..
+                            } => Some(*key),
+                            _ => None,
+                        })
+                    });
+                    if let Some(key) = pressed {
+                        // Consume the press so it doesn't also trigger the action it was bound to
+                        ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, key));
+                        self.settings.key_bindings.set(action, key);
+                        self.listening_for = None;
+                    }
+                }
+
+                for (a, b) in self.settings.key_bindings.conflicts() {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("{} and {} share the same key", a.label(), b.label()),
+                    );
+                }
+            });
+
         egui::TopBottomPanel::top("menu").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.heading("SpinScape");
+                ui.heading(&self.title);
                 if ui.button("Menu").clicked() {
                     self.state = State::Menu;
                 }
+                if matches!(self.state, State::Playing(_) | State::Paused(_))
+                    && ui.button("↺ Restart").clicked()
+                {
+                    if let Err(error) = self.reset_current_level() {
+                        self.state = State::Error(error.to_string());
+                    }
+                }
             });
         });
 
@@ -94,37 +541,636 @@ impl App {
                     "https://github.com/thomasgt/bouncy/blob/main/",
                     "Source code."
                 ));
-                ui.label(format!("FPS: {:.0}", fps.round()));
+                ui.label(format!(
+                    "FPS: {:.0} / {:.0}",
+                    fps.round(),
+                    self.settings.target_fps.round()
+                ));
                 egui::warn_if_debug_build(ui);
             });
         });
     }
 
+    fn draw_debug_window(&mut self, ctx: &egui::Context, fps: f32) {
+        if !self.settings.debug_mode {
+            return;
+        }
+
+        let histogram = Self::frame_time_histogram(&self.previous_frame_times.to_vec());
+        let metrics = self.collect_metrics();
+        let escape_estimate = self.escape_estimate;
+        let mut estimate_requested = false;
+        let game = match &self.state {
+            State::Menu | State::Achievements | State::Stats | State::Error(_) => None,
+            State::LevelIntro { game, .. }
+            | State::StartingGame { game, .. }
+            | State::Playing(game)
+            | State::Paused(game)
+            | State::Victory(game)
+            | State::Defeat(game)
+            | State::Replay { game, .. } => Some(game),
+        };
+
+        egui::Window::new("Physics Debug")
+            .open(&mut self.show_debug_window)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("physics_debug")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("FPS");
+                        ui.label(format!("{:.1}", fps));
+                        ui.end_row();
+
+                        let Some(game) = game else {
+                            return;
+                        };
+
+                        for (index, ball) in game.level.balls.iter().enumerate() {
+                            ui.label(format!("Ball {index} center"));
+                            ui.label(format!("({:.3}, {:.3})", ball.center.x, ball.center.y));
+                            ui.end_row();
+
+                            ui.label(format!("Ball {index} velocity"));
+                            ui.label(format!("({:.3}, {:.3})", ball.velocity.x, ball.velocity.y));
+                            ui.end_row();
+
+                            ui.label(format!("Ball {index} speed"));
+                            ui.label(format!("{:.3}", ball.velocity.length()));
+                            ui.end_row();
+                        }
+
+                        let body = &game.level.body;
+                        ui.label("Body angle");
+                        ui.label(format!("{:.1}°", body.angle.to_degrees()));
+                        ui.end_row();
+
+                        ui.label("Angular velocity");
+                        ui.label(format!("{:.3} rad/s", body.angular_velocity));
+                        ui.end_row();
+
+                        ui.label("Tick");
+                        ui.label(game.tick_counter.to_string());
+                        ui.end_row();
+
+                        ui.label("Frame");
+                        ui.label(game.frame_counter.to_string());
+                        ui.end_row();
+
+                        ui.label("Collisions listed");
+                        ui.label(game.collision_list.len().to_string());
+                        ui.end_row();
+
+                        ui.label("Work remaining");
+                        ui.label(format!("{:.2}", game.work_remaining()));
+                        ui.end_row();
+
+                        ui.label("Optimal escape");
+                        ui.horizontal(|ui| {
+                            match escape_estimate {
+                                Some((tick, Some(time))) => {
+                                    ui.label(format!("{:.2} s (from tick {})", time, tick))
+                                }
+                                Some((tick, None)) => {
+                                    ui.label(format!("None found (from tick {})", tick))
+                                }
+                                None => ui.label("-"),
+                            };
+                            estimate_requested = ui.small_button("Estimate").clicked();
+                        });
+                        ui.end_row();
+                    });
+
+                egui::CollapsingHeader::new("Frame times").show(ui, |ui| {
+                    Self::draw_frame_time_histogram(ui, &histogram);
+                });
+
+                egui::CollapsingHeader::new("Lifetime metrics").show(ui, |ui| {
+                    Self::draw_metrics(ui, &metrics);
+                });
+
+                if let Some(game) = game {
+                    egui::CollapsingHeader::new("Work spent").show(ui, |ui| {
+                        Self::draw_work_graph(ui, game);
+                    });
+                }
+
+                if ui.button("📷").on_hover_text("Save screenshot").clicked() {
+                    // The frame comes back as an event once it has been rendered
+                    ui.ctx()
+                        .send_viewport_cmd(egui::ViewportCommand::Screenshot(Default::default()));
+                }
+            });
+
+        if estimate_requested {
+            self.escape_estimate = game.map(|game| (game.tick_counter, game.optimal_escape_time()));
+        }
+    }
+
+    /// Saves any screenshot that was rendered since the last frame
+    fn handle_screenshot(ctx: &egui::Context) {
+        let image = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        });
+        let Some(image) = image else {
+            return;
+        };
+
+        let filename = Self::screenshot_filename(web_time::SystemTime::now());
+        match Self::save_screenshot(&image, &filename) {
+            Ok(()) => log::info!("Saved screenshot to {}", filename),
+            Err(err) => log::warn!("Failed to save screenshot: {}", err),
+        }
+    }
+
+    /// Names the screenshot after the local time, where the platform provides it
+    fn screenshot_filename(now: web_time::SystemTime) -> String {
+        let secs = now
+            .duration_since(web_time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let local = secs + util::local_utc_offset_secs();
+        format!("screenshot_{}.png", util::format_timestamp(local))
+    }
+
+    /// Writes the image as a PNG file named `filename`
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_screenshot(
+        image: &egui::ColorImage,
+        filename: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        image::save_buffer(
+            filename,
+            image.as_raw(),
+            image.width() as u32,
+            image.height() as u32,
+            image::ExtendedColorType::Rgba8,
+        )?;
+        Ok(())
+    }
+
+    /// Encodes the image as a PNG and has the browser download it as `filename`
+    #[cfg(target_arch = "wasm32")]
+    fn save_screenshot(
+        image: &egui::ColorImage,
+        filename: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use eframe::wasm_bindgen::JsCast as _;
+        use image::ImageEncoder as _;
+
+        let mut png = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut png).write_image(
+            image.as_raw(),
+            image.width() as u32,
+            image.height() as u32,
+            image::ExtendedColorType::Rgba8,
+        )?;
+
+        let js_error = |err: eframe::wasm_bindgen::JsValue| format!("{:?}", err);
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(png.as_slice()));
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type("image/png");
+        let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+            .map_err(js_error)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or("no document to download from")?;
+        let anchor = document
+            .create_element("a")
+            .map_err(js_error)?
+            .dyn_into::<web_sys::HtmlAnchorElement>()
+            .map_err(|_| "not an anchor element")?;
+        anchor.set_href(&url);
+        anchor.set_download(filename);
+        anchor.click();
+
+        // The download starts asynchronously, so revoking the URL straight away can cancel it
+        let revoke = eframe::wasm_bindgen::closure::Closure::once_into_js(move || {
+            let _ = web_sys::Url::revoke_object_url(&url);
+        });
+        web_sys::window()
+            .ok_or("no window to download from")?
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                revoke.unchecked_ref(),
+                URL_REVOKE_DELAY_MS,
+            )
+            .map_err(js_error)?;
+        Ok(())
+    }
+
+    /// Counts the time between consecutive frames in buckets of 5 ms, with anything slower than
+    /// 100 ms going in the last bucket
+    fn frame_time_histogram(frame_times: &[web_time::Instant]) -> [u32; HISTOGRAM_BUCKETS] {
+        let mut histogram = [0; HISTOGRAM_BUCKETS];
+        for pair in frame_times.windows(2) {
+            let millis = (pair[1] - pair[0]).as_secs_f32() * 1000.;
+            let bucket = (millis / HISTOGRAM_BUCKET_MS) as usize;
+            histogram[bucket.min(HISTOGRAM_BUCKETS - 1)] += 1;
+        }
+        histogram
+    }
+
+    fn draw_frame_time_histogram(ui: &mut egui::Ui, histogram: &[u32]) {
+        let size = egui::vec2(ui.available_width().max(200.0), 80.0);
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        let max_count = histogram.iter().copied().max().unwrap_or(0).max(1);
+        let bar_width = rect.width() / histogram.len() as f32;
+        let colour = ui.visuals().selection.bg_fill;
+        for (i, count) in histogram.iter().enumerate() {
+            let height = rect.height() * *count as f32 / max_count as f32;
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(rect.left() + i as f32 * bar_width, rect.bottom() - height),
+                egui::pos2(
+                    rect.left() + (i + 1) as f32 * bar_width - 1.0,
+                    rect.bottom(),
+                ),
+            );
+            painter.rect_filled(bar, 0.0, colour);
+        }
+
+        ui.horizontal(|ui| {
+            ui.small("0 ms");
+            ui.add_space(ui.available_width() - 50.0);
+            ui.small("100+ ms");
+        });
+    }
+
+    fn draw_metrics(ui: &mut egui::Ui, metrics: &AppMetrics) {
+        egui::Grid::new("metrics").striped(true).show(ui, |ui| {
+            ui.label("Physics ticks");
+            ui.label(metrics.total_physics_ticks.to_string());
+            ui.end_row();
+
+            ui.label("Frames played");
+            ui.label(metrics.total_render_frames.to_string());
+            ui.end_row();
+
+            ui.label("Average FPS");
+            ui.label(format!("{:.1}", metrics.average_measured_fps));
+            ui.end_row();
+
+            ui.label("Tick overruns");
+            let overruns = RichText::new(metrics.tick_overrun_count.to_string());
+            ui.label(if metrics.tick_overrun_count > 0 {
+                overruns.color(ui.visuals().warn_fg_color)
+            } else {
+                overruns
+            });
+            ui.end_row();
+
+            ui.label("Collisions");
+            ui.label(metrics.collision_events_lifetime.to_string());
+            ui.end_row();
+
+            ui.label("Sessions");
+            ui.label(metrics.sessions.to_string());
+            ui.end_row();
+        });
+    }
+
+    /// Cumulative brake and boost work against time, over the level's time limit
+    fn draw_work_graph(ui: &mut egui::Ui, game: &Game) {
+        let size = egui::vec2(ui.available_width().max(200.0), 80.0);
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        let now = game.scaled_elapsed.as_secs_f32();
+        let max_time = game.level.max_time.as_secs_f32().max(now).max(1.0);
+        let max_work = (game.level.max_brake_work + game.level.max_boost_work).max(1.0);
+        let to_screen = |time: f32, work: f32| {
+            egui::pos2(
+                egui::remap(time, 0.0..=max_time, rect.x_range()),
+                egui::remap(
+                    work.min(max_work),
+                    0.0..=max_work,
+                    rect.bottom()..=rect.top(),
+                ),
+            )
+        };
+
+        painter.rect_stroke(rect, 0.0, ui.visuals().widgets.noninteractive.bg_stroke);
+
+        let points: Vec<egui::Pos2> = game
+            .work_history
+            .iter()
+            .map(|(time, work)| to_screen(*time, *work))
+            .collect();
+        let colour = ui.visuals().selection.bg_fill;
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, colour)));
+
+        let present = [to_screen(now, 0.0), to_screen(now, max_work)];
+        painter.extend(egui::Shape::dotted_line(
+            &present,
+            ui.visuals().weak_text_color(),
+            4.0,
+            1.0,
+        ));
+
+        ui.horizontal(|ui| {
+            ui.small("0 s");
+            ui.add_space(ui.available_width() - 50.0);
+            ui.small(format!("{:.0} s", max_time));
+        });
+    }
+
     fn handle_menu(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) -> Option<State> {
-        let mut new_state = None;
+        let mut selected = None;
+        let mut next_screen = None;
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 ui.label("Select a level to play:");
+                ui.horizontal(|ui| {
+                    if ui.button("🏆 Achievements").clicked() {
+                        next_screen = Some(State::Achievements);
+                    }
+                    if ui.button("📊 Stats").clicked() {
+                        next_screen = Some(State::Stats);
+                    }
+                });
 
                 for level in &self.levels {
-                    if ui.button(&level.name).clicked() {
-                        new_state = Some(State::Playing(Game::new(level.clone(), 1024.)));
-                    }
+                    ui.horizontal(|ui| {
+                        let play = ui.button(&level.name).clicked();
+                        let practice = ui.small_button("Practice").clicked();
+
+                        if self.completed_levels.contains(&level.id_name) {
+                            ui.colored_label(Color32::GREEN, "✓");
+                        }
+                        if let Some(best_time) = self.best_times.get(&level.id_name) {
+                            ui.label(format!("Best: {:.1}s", best_time));
+                        }
+
+                        if play || practice {
+                            selected = Some((level.clone(), practice));
+                        }
+                    });
                 }
             });
         });
 
+        if next_screen.is_some() {
+            return next_screen;
+        }
+        selected.map(|(level, practice)| self.start_level(&level, practice))
+    }
+
+    /// Formats a duration as H:MM:SS
+    fn format_playtime(secs: f64) -> String {
+        let secs = secs.max(0.0) as u64;
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    }
+
+    /// Play time including the current session
+    fn playtime_secs(&self) -> f64 {
+        self.total_playtime_secs + self.session_start.elapsed().as_secs_f64()
+    }
+
+    fn handle_stats(&mut self, ctx: &egui::Context) -> Option<State> {
+        let mut new_state = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("⬅ Back").clicked() {
+                    new_state = Some(State::Menu);
+                }
+                ui.heading("Stats");
+            });
+
+            egui::Grid::new("stats").striped(true).show(ui, |ui| {
+                ui.label("Total play time");
+                ui.label(Self::format_playtime(self.playtime_secs()));
+                ui.end_row();
+
+                ui.label("Sessions");
+                ui.label((self.session_count + 1).to_string());
+                ui.end_row();
+
+                ui.label("Levels completed");
+                ui.label(format!(
+                    "{} / {}",
+                    self.completed_levels.len(),
+                    self.levels.len()
+                ));
+                ui.end_row();
+            });
+        });
+
+        // Keep the play time ticking over
+        ctx.request_repaint_after(web_time::Duration::from_secs(1));
+
+        new_state
+    }
+
+    fn handle_achievements(&mut self, ctx: &egui::Context) -> Option<State> {
+        let mut new_state = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("⬅ Back").clicked() {
+                    new_state = Some(State::Menu);
+                }
+                ui.heading("Achievements");
+            });
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("achievements")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for achievement in Achievement::all(&self.levels) {
+                            let unlocked = self.achievements.contains(&achievement.key());
+                            let title = RichText::new(achievement.title()).strong();
+                            if unlocked {
+                                ui.label(RichText::new("🏆").color(Color32::GOLD));
+                                ui.label(title);
+                            } else {
+                                ui.label("🔒");
+                                ui.label(title.weak());
+                            }
+                            ui.label(achievement.description(&self.levels));
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+
         new_state
     }
 
-    fn handle_game(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) -> Option<State> {
+    /// Starts a fresh run of `level`, along with a ghost of its latest winning run if there is one
+    fn start_level(&mut self, level: &Level, practice: bool) -> State {
+        State::LevelIntro {
+            game: self.prepare_game(level, practice),
+            intro_start: web_time::Instant::now(),
+        }
+    }
+
+    /// A fresh game of the level, with the ghost of the best regular run set up alongside it
+    fn prepare_game(&mut self, level: &Level, practice: bool) -> Game {
+        let level = Level {
+            is_practice: practice,
+            ..level.clone()
+        };
+
+        // Ghosts always come from regular runs, so replay them as one
+        self.ghost_game = self.ghost_recordings.get(&level.id_name).map(|log| {
+            let level = Level {
+                is_practice: false,
+                ..level.clone()
+            };
+            Game::new(level, self.tick_rate).with_replay(log.clone())
+        });
+
+        Self::new_game(level, self.tick_rate)
+    }
+
+    fn new_game(level: Level, tick_rate: f32) -> Game {
+        let mut game = Game::new(level, tick_rate).with_history(4096);
+        game.record_inputs = true;
+        game
+    }
+
+    /// Restarts the level being played from scratch, discarding the current run. Skips the intro
+    /// and countdown, so the player is straight back in.
+    fn reset_current_level(&mut self) -> Result<(), GameError> {
+        let (State::Playing(game) | State::Paused(game)) = &self.state else {
+            return Ok(());
+        };
+
+        let practice = game.speed_run_mode;
+        let level = self
+            .levels
+            .iter()
+            .find(|level| level.id_name == game.level.id_name)
+            .cloned()
+            .ok_or(GameError::LevelNotFound(game.level.id))?;
+        self.state = State::Playing(self.prepare_game(&level, practice));
+        Ok(())
+    }
+
+    fn invalid_state(&self) -> GameError {
+        GameError::InvalidState {
+            found: self.state.name().to_owned(),
+        }
+    }
+
+    /// Scale of the world while zooming in at the start of a level
+    fn intro_zoom(elapsed: web_time::Duration) -> f32 {
+        let alpha = (elapsed.as_secs_f32() / INTRO_DURATION.as_secs_f32()).clamp(0.0, 1.0);
+        egui::lerp(0.1..=1.0, alpha * alpha)
+    }
+
+    fn handle_level_intro(
+        &mut self,
+        ctx: &egui::Context,
+        _frame: &mut eframe::Frame,
+    ) -> Result<Option<State>, GameError> {
+        let (game, intro_start) = if let State::LevelIntro { game, intro_start } = &self.state {
+            (game, intro_start)
+        } else {
+            return Err(self.invalid_state());
+        };
+
+        ctx.request_repaint_after(Self::repaint_interval(self.settings.target_fps));
+
+        let elapsed = intro_start.elapsed();
+        Self::draw_world(ctx, game, None, Self::intro_zoom(elapsed), &self.settings);
+
+        Ok((elapsed >= INTRO_DURATION).then(|| State::StartingGame {
+            game: game.clone(),
+            countdown_start: web_time::Instant::now(),
+        }))
+    }
+
+    /// Text shown at each point of the countdown, or `None` once it is over
+    fn countdown_label(elapsed: web_time::Duration) -> Option<&'static str> {
+        if elapsed >= COUNTDOWN_DURATION {
+            return None;
+        }
+
+        Some(match elapsed.as_secs() {
+            0 => "3",
+            1 => "2",
+            2 => "1",
+            _ => "GO!",
+        })
+    }
+
+    fn handle_starting_game(
+        &mut self,
+        ctx: &egui::Context,
+        _frame: &mut eframe::Frame,
+    ) -> Result<Option<State>, GameError> {
+        let (game, countdown_start) = if let State::StartingGame {
+            game,
+            countdown_start,
+        } = &mut self.state
+        {
+            (game, countdown_start)
+        } else {
+            return Err(self.invalid_state());
+        };
+
+        ctx.request_repaint_after(Self::repaint_interval(self.settings.target_fps));
+
+        let ghost = self.ghost_game.as_ref().filter(|_| self.show_ghost);
+        Self::draw_world(ctx, game, ghost, 1.0, &self.settings);
+
+        let skipped = ctx.input(|i| i.pointer.any_pressed());
+        let Some(label) = Self::countdown_label(countdown_start.elapsed()).filter(|_| !skipped)
+        else {
+            // The clock only starts once the countdown is over
+            game.start_time = web_time::Instant::now();
+            return Ok(Some(State::Playing(game.clone())));
+        };
+
+        egui::Area::new(egui::Id::new("countdown"))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(label).size(96.0).strong());
+            });
+
+        Ok(None)
+    }
+
+    fn handle_game(
+        &mut self,
+        ctx: &egui::Context,
+        _frame: &mut eframe::Frame,
+    ) -> Result<Option<State>, GameError> {
         let game = if let State::Playing(game) = &mut self.state {
             game
         } else {
-            panic!("Invalid game state");
+            return Err(self.invalid_state());
         };
 
-        let game_state = game.update();
+        // Any fresh press dismisses the tutorial message currently on screen
+        let input_pressed = ctx.input(|i| {
+            i.pointer.any_pressed()
+                || i.events
+                    .iter()
+                    .any(|event| matches!(event, egui::Event::Key { pressed: true, .. }))
+        });
+        if input_pressed {
+            game.tutorial_state.dismiss();
+        }
+
+        let game_state = self.metrics.track_update(game);
+        game.check_finite()?;
+
+        // Keep the ghost in step with the live game and drop it once it has escaped
+        if let Some(ghost) = self.ghost_game.as_mut() {
+            ghost.advance_to(game.tick_counter);
+            if ghost.has_escaped() {
+                self.ghost_game = None;
+            }
+        }
+
         let next_state = match game_state {
             game::State::Victory => Some(State::Victory(game.clone())),
             game::State::Defeat => Some(State::Defeat(game.clone())),
@@ -132,63 +1178,279 @@ impl App {
         };
 
         // Schedule a repaint at the next frame
-        ctx.request_repaint_after(web_time::Duration::from_secs_f32(
-            1.0 / self.target_frame_rate,
-        ));
+        ctx.request_repaint_after(Self::repaint_interval(self.settings.target_fps));
+
+        let pause_key = self.settings.key_bindings.pause;
+        let mut pause_requested =
+            self.listening_for.is_none() && ctx.input(|i| i.key_pressed(pause_key));
 
         egui::TopBottomPanel::top("countdown")
             .show_separator_line(false)
             .show(ctx, |ui| {
-                let elapsed = (web_time::Instant::now() - game.start_time).as_secs_f32();
+                let elapsed = game.scaled_elapsed.as_secs_f32();
                 let limit = game.level.max_time.as_secs_f32();
-                let remaining = limit - elapsed;
+                let remaining = (limit - elapsed).max(0.0);
                 let time_progress = remaining / limit;
 
+                ui.horizontal(|ui| {
+                    if ui.button("⏸").clicked() {
+                        pause_requested = true;
+                    }
+                    ui.add(TimerRing::new(time_progress).with_text(format!("{:.1}s", remaining)));
+                });
+
+                if !game.level.checkpoints.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        for (index, split) in game.checkpoint_times.iter().enumerate() {
+                            match split {
+                                Some(split) => ui.label(format!("CP{}: {:.1}s", index + 1, split)),
+                                None => ui.weak(format!("CP{}: --", index + 1)),
+                            };
+                        }
+                    });
+                }
+
+                let brake_progress = game.input_work.brake_fraction(game.brake_budget());
                 ui.add(
-                    egui::ProgressBar::new(time_progress)
-                        .text(format!("Time remaining: {:.1} s", remaining)),
+                    egui::ProgressBar::new(brake_progress)
+                        .fill(Color32::LIGHT_RED)
+                        .text(format!(
+                            "🔴 Brake: {:.0} %",
+                            (brake_progress * 100.).round()
+                        )),
                 );
 
-                let work_remaining = game.work_remaining();
-                let work_progress = work_remaining / game.level.max_work;
-                ui.add(egui::ProgressBar::new(work_progress).text(format!(
-                    "Power remaining: {:.0} %",
-                    (work_progress * 100.).round()
-                )));
+                let boost_progress = game.input_work.boost_fraction(game.boost_budget());
+                ui.add(
+                    egui::ProgressBar::new(boost_progress)
+                        .fill(Color32::LIGHT_GREEN)
+                        .text(format!(
+                            "🟢 Boost: {:.0} %",
+                            (boost_progress * 100.).round()
+                        )),
+                );
+
+                ui.label(format!(
+                    "Escape progress: {:.0}%",
+                    (game.escape_progress() * 100.).round()
+                ));
+
+                if self.ghost_game.is_some() {
+                    ui.checkbox(&mut self.show_ghost, "👻 Show ghost");
+                }
+
+                if game.score > 0 {
+                    ui.label(format!(
+                        "Score: {}  Combo: x{:.1}",
+                        game.score,
+                        game.score_multiplier()
+                    ));
+                }
             });
 
         egui::TopBottomPanel::bottom("controls")
             .show_separator_line(false)
             .show(ctx, |ui| {
-                ui.add_enabled_ui(game.inputs_enabled(), |ui| {
-                    ui.columns(4, |ui| {
-                        let brake_button = ui[1].add_sized(
-                            egui::vec2(50.0, 50.0),
-                            egui::Button::new(
-                                RichText::new("Brake")
-                                    .strong()
-                                    .heading()
-                                    .color(Color32::BLACK),
-                            )
-                            .fill(Color32::LIGHT_RED),
-                        );
+                let (brake_enabled, boost_enabled) = (game.brake_enabled(), game.boost_enabled());
+                ui.columns(5, |ui| {
+                    let rewind_button = ui[0].add_enabled(
+                        game.can_rewind(),
+                        egui::Button::new(
+                            RichText::new(format!("⏪ Rewind ({:.0})", game.level.rewind_cost))
+                                .strong(),
+                        )
+                        .min_size(egui::vec2(50.0, 50.0)),
+                    );
+                    if rewind_button.clicked() {
+                        // can_rewind has already checked everything that could fail here
+                        let _ = game.rewind();
+                    }
+
+                    // Touch screens get the joystick below instead
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        let brake_button = ui[1]
+                            .add_enabled_ui(brake_enabled, |ui| {
+                                ui.add_sized(
+                                    egui::vec2(50.0, 50.0),
+                                    egui::Button::new(
+                                        RichText::new("Brake")
+                                            .strong()
+                                            .heading()
+                                            .color(Color32::BLACK),
+                                    )
+                                    .fill(Color32::LIGHT_RED),
+                                )
+                            })
+                            .inner;
                         game.level.input.brake.active = brake_button.is_pointer_button_down_on();
 
-                        let boost_button = ui[2].add_sized(
-                            egui::vec2(50.0, 50.0),
-                            egui::Button::new(
-                                RichText::new("Boost")
-                                    .strong()
-                                    .heading()
-                                    .color(Color32::BLACK),
-                            )
-                            .fill(Color32::LIGHT_GREEN),
-                        );
+                        let boost_button = ui[2]
+                            .add_enabled_ui(boost_enabled, |ui| {
+                                ui.add_sized(
+                                    egui::vec2(50.0, 50.0),
+                                    egui::Button::new(
+                                        RichText::new("Boost")
+                                            .strong()
+                                            .heading()
+                                            .color(Color32::BLACK),
+                                    )
+                                    .fill(Color32::LIGHT_GREEN),
+                                )
+                            })
+                            .inner;
                         game.level.input.boost.active = boost_button.is_pointer_button_down_on();
+                    }
+
+                    Self::motor_button(&mut ui[3], &mut game.level.input.motor);
+
+                    let slow_mo_button = ui[4].add_sized(
+                        egui::vec2(50.0, 50.0),
+                        egui::Button::new(RichText::new("🐌 Slow-Mo").strong()),
+                    );
+                    game.time_scale = if slow_mo_button.is_pointer_button_down_on() {
+                        0.25
+                    } else {
+                        1.0
+                    };
+                });
+
+                #[cfg(target_arch = "wasm32")]
+                {
+                    let (brake, boost) = ui
+                        .add_enabled_ui(brake_enabled || boost_enabled, |ui| {
+                            crate::ui::joystick::VirtualJoystick::default().show(ui)
+                        })
+                        .inner;
+                    game.level.input.brake.active = brake;
+                    game.level.input.boost.active = boost;
+                }
+
+                if self.listening_for.is_none() {
+                    Self::apply_key_input(
+                        ui.ctx(),
+                        &self.settings.key_bindings,
+                        &mut game.level.input,
+                    );
+                }
+
+                // Fast balls are the ones at risk of tunnelling through a wall
+                let speed = game
+                    .level
+                    .balls
+                    .iter()
+                    .map(|ball| ball.velocity.length())
+                    .fold(0.0, f32::max);
+                let speed_text = RichText::new(format!("Speed: {:.1}", speed));
+                ui.label(if speed > SPEED_WARNING_THRESHOLD {
+                    speed_text.color(ui.visuals().error_fg_color)
+                } else {
+                    speed_text
+                });
+            });
+
+        let ghost = self.ghost_game.as_ref().filter(|_| self.show_ghost);
+        Self::draw_world(ctx, game, ghost, 1.0, &self.settings);
+
+        if let Some(message) = game.tutorial_message() {
+            egui::Area::new(egui::Id::new("tutorial"))
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .interactable(false)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(RichText::new(message).heading().strong());
                     });
                 });
+        }
+
+        if next_state.is_none() && pause_requested {
+            game.pause();
+            return Ok(Some(State::Paused(game.clone())));
+        }
+
+        Ok(next_state)
+    }
+
+    fn handle_paused(
+        &mut self,
+        ctx: &egui::Context,
+        _frame: &mut eframe::Frame,
+    ) -> Result<Option<State>, GameError> {
+        let game = if let State::Paused(game) = &mut self.state {
+            game
+        } else {
+            return Err(self.invalid_state());
+        };
+
+        let ghost = self.ghost_game.as_ref().filter(|_| self.show_ghost);
+        Self::draw_world(ctx, game, ghost, 1.0, &self.settings);
+
+        let mut new_state = None;
+        let pause_key = self.settings.key_bindings.pause;
+        let mut resume = self.listening_for.is_none() && ctx.input(|i| i.key_pressed(pause_key));
+
+        let frame = egui::Frame::window(&ctx.style()).multiply_with_opacity(0.85);
+        egui::Window::new("Paused")
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .collapsible(false)
+            .resizable(false)
+            .frame(frame)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    resume |= ui.button("▶ Resume").clicked();
+                    if ui.button("🏠 Quit to Menu").clicked() {
+                        new_state = Some(State::Menu);
+                    }
+                });
             });
 
+        if new_state.is_none() && resume {
+            game.resume();
+            new_state = Some(State::Playing(game.clone()));
+        }
+
+        Ok(new_state)
+    }
+
+    /// Toggles the motor on click, so the player can cut power and coast
+    fn motor_button(ui: &mut egui::Ui, motor: &mut Input) -> egui::Response {
+        let label = format!(
+            "⚙ Motor: {} ({:.1})",
+            if motor.active { "ON" } else { "OFF" },
+            motor.torque
+        );
+        let response = ui.add_sized(
+            egui::vec2(50.0, 50.0),
+            egui::Button::new(RichText::new(label).strong()).selected(motor.active),
+        );
+        if response.clicked() {
+            motor.active = !motor.active;
+        }
+        response
+    }
+
+    /// Adds keyboard controls on top of the on-screen buttons, so either can be used.
+    /// Space also boosts unless it has been bound to something else.
+    fn apply_key_input(ctx: &egui::Context, bindings: &KeyBindings, input: &mut InputSet) {
+        let space_boosts = !bindings.is_bound(egui::Key::Space);
+        let (brake_held, boost_held) = ctx.input(|i| {
+            (
+                i.key_down(bindings.brake),
+                i.key_down(bindings.boost) || (space_boosts && i.key_down(egui::Key::Space)),
+            )
+        });
+
+        input.brake.active |= brake_held;
+        input.boost.active |= boost_held;
+    }
+
+    fn draw_world(
+        ctx: &egui::Context,
+        game: &Game,
+        ghost: Option<&Game>,
+        zoom: f32,
+        settings: &AppSettings,
+    ) {
         egui::CentralPanel::default().show(ctx, |ui| {
             let available_size = ui.available_size();
 
@@ -197,49 +1459,265 @@ impl App {
 
             let canvas_rect = response.rect;
 
-            // Define scaling factor so hexagon takes up 80% of the available space
-            let max_extent = game
-                .level
-                .body
-                .shape
-                .max_extent(game.level.body.center_of_rotation);
-
-            let left_top_radius = max_extent.min.to_vec2().length();
-            let bottom_right_radius = max_extent.max.to_vec2().length();
-            let radius = left_top_radius.max(bottom_right_radius);
+            let center = canvas_rect.center().to_vec2();
+            let transform = TSTransform::from_translation(center)
+                * TSTransform::from_scaling(zoom)
+                * TSTransform::from_translation(-center)
+                * game.view_transform(canvas_rect);
 
-            let scale = 0.8 * canvas_rect.size().min_elem() / (2. * radius);
+            game.draw_background(&painter, canvas_rect);
 
-            let transform = TSTransform {
-                scaling: scale,
-                translation: canvas_rect.center().to_vec2(),
-            };
+            // Faint level name in the corner, shown fully when debugging
+            let alpha = if settings.debug_mode { 255 } else { 60 };
+            let colour = ui.visuals().text_color();
+            painter.text(
+                canvas_rect.left_top() + egui::vec2(8.0, 8.0),
+                egui::Align2::LEFT_TOP,
+                &game.level.name,
+                egui::FontId::proportional(14.0),
+                Color32::from_rgba_unmultiplied(colour.r(), colour.g(), colour.b(), alpha),
+            );
 
+            game.level.gravity_wells.iter().for_each(|well| {
+                well.draw(ctx, &painter, transform);
+            });
+            if settings.debug_mode {
+                game.level.force_fields.iter().for_each(|field| {
+                    field.draw(ctx, &painter, transform);
+                });
+            }
             game.level.body.draw(ctx, &painter, transform);
-            game.level.ball.draw(ctx, &painter, transform);
-            game.collision_list.iter().for_each(|collision| {
-                collision.draw(ctx, &painter, transform);
+            game.draw_escape_zones(&painter, transform);
+            game.draw_checkpoints(&painter, transform);
+            game.level.moving_platform.iter().for_each(|platform| {
+                platform.draw(ctx, &painter, transform);
+            });
+            if settings.show_ball_trail {
+                game.draw_trails(&painter, transform);
+            }
+            if let Some(ghost) = ghost {
+                ghost.level.balls.iter().for_each(|ball| {
+                    ball.draw_ghost(&painter, transform, 0.5);
+                });
+            }
+            game.level.balls.iter().for_each(|ball| {
+                ball.draw(ctx, &painter, transform);
             });
+            if settings.collision_sparkles {
+                game.collision_list.iter().for_each(|collision| {
+                    collision.draw(ctx, &painter, transform);
+                });
+            }
         });
+    }
 
-        next_state
+    fn handle_replay(
+        &mut self,
+        ctx: &egui::Context,
+        _frame: &mut eframe::Frame,
+    ) -> Result<Option<State>, GameError> {
+        let (game, original) = if let State::Replay { game, original } = &mut self.state {
+            (game, original)
+        } else {
+            return Err(self.invalid_state());
+        };
+
+        let mut finished = !matches!(game.update(), game::State::Playing);
+
+        ctx.request_repaint_after(Self::repaint_interval(self.settings.target_fps));
+
+        egui::TopBottomPanel::top("replay").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Replay: {:.1} s",
+                    game.scaled_elapsed.as_secs_f32()
+                ));
+                if ui.button("⏹ Stop").clicked() {
+                    finished = true;
+                }
+            });
+        });
+
+        Self::draw_world(ctx, game, None, 1.0, &self.settings);
+
+        Ok(finished.then(|| State::Victory(original.as_ref().clone())))
+    }
+
+    fn draw_run_stats(ui: &mut egui::Ui, stats: &RunStats) {
+        egui::Grid::new("run_stats").striped(true).show(ui, |ui| {
+            ui.label("Collisions");
+            ui.label(stats.total_collisions.to_string());
+            ui.end_row();
+
+            ui.label("Distance traveled");
+            ui.label(format!("{:.2}", stats.distance_traveled));
+            ui.end_row();
+
+            ui.label("Max speed");
+            ui.label(format!("{:.2}", stats.max_speed));
+            ui.end_row();
+
+            ui.label("Brake work");
+            ui.label(format!("{:.1}", stats.work_spent_brake));
+            ui.end_row();
+
+            ui.label("Boost work");
+            ui.label(format!("{:.1}", stats.work_spent_boost));
+            ui.end_row();
+        });
     }
 
-    fn handle_victory(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) -> Option<State> {
+    /// Table of each checkpoint's split time, followed by the total
+    fn draw_split_times(ui: &mut egui::Ui, game: &Game) {
+        if game.level.checkpoints.is_empty() {
+            return;
+        }
+
+        egui::Grid::new("split_times").striped(true).show(ui, |ui| {
+            for (index, (checkpoint, split)) in game
+                .level
+                .checkpoints
+                .iter()
+                .zip(&game.checkpoint_times)
+                .enumerate()
+            {
+                ui.label(format!("CP{}", index + 1));
+                ui.label(&checkpoint.label);
+                ui.label(split.map_or("--".to_owned(), |split| format!("{:.1} s", split)));
+                ui.end_row();
+            }
+
+            ui.strong("Total");
+            ui.label("");
+            ui.strong(format!("{:.1} s", game.scaled_elapsed.as_secs_f32()));
+            ui.end_row();
+        });
+    }
+
+    /// Bursts particles outwards from the ball that got furthest out of the shape
+    fn spawn_victory_particles(&mut self, game: &Game) {
+        let center = game.level.body.center_of_rotation;
+        let Some(ball) = game.level.balls.iter().max_by(|a, b| {
+            let distance = |ball: &&crate::ball::Ball| (ball.center - center).length();
+            distance(a).total_cmp(&distance(b))
+        }) else {
+            return;
+        };
+
+        let direction = ball.center - center;
+        self.victory_particles = particles::burst(ball.center, direction, 50, game.tick_counter);
+        self.last_particle_update = web_time::Instant::now();
+    }
+
+    fn draw_victory_particles(&mut self, ctx: &egui::Context, transform: TSTransform) {
+        if self.victory_particles.is_empty() {
+            return;
+        }
+
+        let now = web_time::Instant::now();
+        let dt = (now - self.last_particle_update).as_secs_f32();
+        self.last_particle_update = now;
+        particles::update(&mut self.victory_particles, dt);
+
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("victory_particles"),
+        ));
+        particles::draw(&self.victory_particles, &painter, transform);
+
+        // Stop animating once the last particle has died
+        if !self.victory_particles.is_empty() {
+            ctx.request_repaint_after(web_time::Duration::from_millis(16));
+        }
+    }
+
+    fn handle_victory(
+        &mut self,
+        ctx: &egui::Context,
+        _frame: &mut eframe::Frame,
+    ) -> Result<Option<State>, GameError> {
+        let game = if let State::Victory(game) = &self.state {
+            game
+        } else {
+            return Err(self.invalid_state());
+        };
+
+        let rating = game.rating();
+        let stars: String = (1..=3)
+            .map(|star| if star <= rating { '★' } else { '☆' })
+            .collect();
+        let best_rating = self
+            .best_ratings
+            .get(&game.level.id_name)
+            .copied()
+            .unwrap_or(rating);
+
         let mut new_state = None;
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 ui.label("Congratulations! You have won!");
+                if game.speed_run_mode {
+                    ui.label(format!(
+                        "Practice time: {:.1} s",
+                        game.scaled_elapsed.as_secs_f32()
+                    ));
+                    if let Some(best_time) = self.speedrun_times.get(&game.level.id_name) {
+                        ui.label(format!("Best practice time: {:.1} s", best_time));
+                    }
+                } else {
+                    ui.label(RichText::new(stars).heading().color(Color32::GOLD));
+                    ui.label(format!("Best: {} / 3", best_rating));
+                    ui.label(format!("Score: {}", game.score));
+                    if let Some(best_score) = self.best_scores.get(&game.level.id_name) {
+                        ui.label(format!("Best score: {}", best_score));
+                    }
+                }
                 if ui.button("Play again").clicked() {
                     new_state = Some(State::Menu);
                 }
+                if ui.button("History").clicked() {
+                    self.show_run_history = true;
+                }
+                if ui.button("Watch Replay").clicked() {
+                    let level = self
+                        .levels
+                        .iter()
+                        .find(|level| level.id_name == game.level.id_name);
+                    if let Some(level) = level {
+                        let level = Level {
+                            is_practice: game.speed_run_mode,
+                            ..level.clone()
+                        };
+                        let replay =
+                            Game::new(level, game.tick_rate).with_replay(game.input_log.clone());
+                        new_state = Some(State::Replay {
+                            game: replay,
+                            original: Box::new(game.clone()),
+                        });
+                    }
+                }
+                Self::draw_split_times(ui, game);
+                Self::draw_run_stats(ui, &game.stats);
             });
         });
 
-        new_state
+        let transform = game.view_transform(ctx.screen_rect());
+        self.draw_victory_particles(ctx, transform);
+
+        Ok(new_state)
     }
 
-    fn draw_defeat(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) -> Option<State> {
+    fn draw_defeat(
+        &mut self,
+        ctx: &egui::Context,
+        _frame: &mut eframe::Frame,
+    ) -> Result<Option<State>, GameError> {
+        let game = if let State::Defeat(game) = &self.state {
+            game
+        } else {
+            return Err(self.invalid_state());
+        };
+
         let mut new_state = None;
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
@@ -247,6 +1725,25 @@ impl App {
                 if ui.button("Try again").clicked() {
                     new_state = Some(State::Menu);
                 }
+                if ui.button("History").clicked() {
+                    self.show_run_history = true;
+                }
+                Self::draw_run_stats(ui, &game.stats);
+            });
+        });
+
+        Ok(new_state)
+    }
+
+    fn draw_error(ctx: &egui::Context, message: &str) -> Option<State> {
+        let mut new_state = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading("Something went wrong");
+                ui.colored_label(ui.visuals().error_fg_color, message);
+                if ui.button("Back to menu").clicked() {
+                    new_state = Some(State::Menu);
+                }
             });
         });
 
@@ -256,28 +1753,321 @@ impl App {
 
 impl eframe::App for App {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        if let State::Playing(game) = &self.state {
-            eframe::set_value(storage, "current_level", &game.level.id);
+        if let State::LevelIntro { game, .. }
+        | State::StartingGame { game, .. }
+        | State::Playing(game)
+        | State::Paused(game) = &self.state
+        {
+            eframe::set_value(storage, "current_level", &game.level.id_name);
         } else {
             eframe::set_value(storage, "current_level", &self.current_level);
         }
+        eframe::set_value(storage, "settings", &self.settings);
+
+        for (id_name, rating) in &self.best_ratings {
+            eframe::set_value(storage, &format!("rating_{}", id_name), rating);
+        }
+        for (id_name, score) in &self.best_scores {
+            eframe::set_value(storage, &format!("score_{}", id_name), score);
+        }
+        for (id_name, time) in &self.speedrun_times {
+            eframe::set_value(storage, &format!("speedrun_time_{}", id_name), time);
+        }
+        for (id_name, time) in &self.best_times {
+            eframe::set_value(storage, &format!("best_time_{}", id_name), time);
+        }
+        for (id_name, history) in &self.run_histories {
+            if let Ok(json) = serde_json::to_string(history) {
+                storage.set_string(&format!("history_{}", id_name), json);
+            }
+        }
+        eframe::set_value(storage, "completed_levels", &self.completed_levels);
+        eframe::set_value(storage, "achievements", &self.achievements);
+        // Saving happens periodically, so count this session on top of the earlier ones rather
+        // than accumulating into them
+        eframe::set_value(storage, "total_playtime_secs", &self.playtime_secs());
+        eframe::set_value(storage, "session_count", &(self.session_count + 1));
+        eframe::set_value(storage, "metrics", &self.collect_metrics());
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.previous_frame_times.push(web_time::Instant::now());
+        let now = web_time::Instant::now();
+        if let (State::Playing(_), Some(previous)) = (&self.state, self.previous_frame_times.back())
+        {
+            self.metrics.track_frame((now - *previous).as_secs_f64());
+        }
+        self.previous_frame_times.push(now);
         let fps = self.compute_fps();
 
         self.draw_chrome(ctx, _frame, fps);
 
+        let restart_key = self.settings.key_bindings.restart;
+        if self.listening_for.is_none() && ctx.input(|i| i.key_pressed(restart_key)) {
+            if let Err(error) = self.reset_current_level() {
+                self.state = State::Error(error.to_string());
+            }
+        }
+
+        let was_playing = matches!(self.state, State::Playing(_));
         let new_state = match &self.state {
-            State::Menu => self.handle_menu(ctx, _frame),
+            State::Menu => Ok(self.handle_menu(ctx, _frame)),
+            State::Achievements => Ok(self.handle_achievements(ctx)),
+            State::Stats => Ok(self.handle_stats(ctx)),
+            State::LevelIntro { .. } => self.handle_level_intro(ctx, _frame),
+            State::StartingGame { .. } => self.handle_starting_game(ctx, _frame),
             State::Playing(_) => self.handle_game(ctx, _frame),
-            State::Victory(snapshot) => self.handle_victory(ctx, _frame),
-            State::Defeat(snapshot) => self.draw_defeat(ctx, _frame),
+            State::Paused(_) => self.handle_paused(ctx, _frame),
+            State::Victory(_) => self.handle_victory(ctx, _frame),
+            State::Defeat(_) => self.draw_defeat(ctx, _frame),
+            State::Replay { .. } => self.handle_replay(ctx, _frame),
+            State::Error(message) => Ok(Self::draw_error(ctx, message)),
         };
+        let new_state = new_state.unwrap_or_else(|error| Some(State::Error(error.to_string())));
 
         if let Some(new_state) = new_state {
+            match (was_playing, &new_state) {
+                (true, State::Victory(game)) => {
+                    self.record_run(game, Outcome::Victory);
+                    self.record_victory(game);
+                    self.unlock_achievements(game);
+                    self.spawn_victory_particles(game);
+                }
+                (true, State::Defeat(game)) => self.record_run(game, Outcome::Defeat),
+                _ => {}
+            }
             self.state = new_state;
         }
+
+        self.draw_run_history(ctx);
+        self.draw_achievement_toasts(ctx);
+        self.draw_debug_window(ctx, fps);
+        Self::handle_screenshot(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_game_starts_fresh() {
+        let game = App::new_game(Level::simple_polygon(6), 1024.);
+
+        assert_eq!(game.tick_counter, 0);
+        assert!(game.start_time.elapsed() < web_time::Duration::from_millis(100));
+        assert!(game.record_inputs);
+        assert!(game.tick_history.is_some());
+    }
+
+    #[test]
+    fn test_restart_goes_straight_to_playing() {
+        let level = Level::simple_polygon(6);
+        let bundle = LevelBundle::from_level(level.clone());
+        let mut app = App::from_storage(None, bundle);
+
+        let mut game = App::new_game(level, app.tick_rate);
+        game.start_time -= web_time::Duration::from_secs(5);
+        game.update();
+        assert!(game.tick_counter > 0);
+        let old_start = game.start_time;
+        app.state = State::Playing(game);
+
+        app.reset_current_level().unwrap();
+        let State::Playing(game) = &app.state else {
+            panic!("Restarted into {}", app.state.name());
+        };
+        assert_eq!(game.tick_counter, 0);
+        assert!(game.start_time > old_start);
+        assert!(game.start_time.elapsed() < web_time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_force_fields_drawn_in_debug_mode() {
+        let mut level = Level::simple_polygon(6);
+        level.force_fields = vec![crate::field::ForceField {
+            region: egui::Rect::from_center_size(egui::Pos2::ZERO, egui::Vec2::splat(0.5)),
+            force: egui::Vec2::new(1.0, 0.0),
+        }];
+        let game = Game::new(level, 1024.);
+
+        let line_segments = |debug_mode: bool| {
+            let settings = AppSettings {
+                debug_mode,
+                ..Default::default()
+            };
+            let ctx = egui::Context::default();
+            let output = ctx.run(egui::RawInput::default(), |ctx| {
+                App::draw_world(ctx, &game, None, 1.0, &settings);
+            });
+            output
+                .shapes
+                .iter()
+                .filter(|clipped| matches!(clipped.shape, egui::Shape::LineSegment { .. }))
+                .count()
+        };
+
+        // The arrow is a shaft and two lines for its head
+        assert_eq!(line_segments(true), line_segments(false) + 3);
+    }
+
+    #[test]
+    fn test_checkpoints_drawn_in_game() {
+        let circles = |level: Level| {
+            let game = Game::new(level, 1024.);
+            let ctx = egui::Context::default();
+            let output = ctx.run(egui::RawInput::default(), |ctx| {
+                App::draw_world(ctx, &game, None, 1.0, &AppSettings::default());
+            });
+            output
+                .shapes
+                .iter()
+                .filter(|clipped| matches!(clipped.shape, egui::Shape::Circle(_)))
+                .count()
+        };
+
+        let mut level = Level::simple_polygon(6);
+        let without = circles(level.clone());
+        level.checkpoints = vec![
+            crate::checkpoint::Checkpoint::new(egui::Pos2::new(0.5, 0.0), 0.1, "Right"),
+            crate::checkpoint::Checkpoint::new(egui::Pos2::new(-0.5, 0.0), 0.1, "Left"),
+        ];
+        assert_eq!(circles(level), without + 2);
+    }
+
+    #[test]
+    fn test_arrow_left_brakes() {
+        let ctx = egui::Context::default();
+        let mut input = Level::simple_polygon(6).input;
+
+        let raw_input = egui::RawInput {
+            events: vec![egui::Event::Key {
+                key: egui::Key::ArrowLeft,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: egui::Modifiers::NONE,
+            }],
+            ..Default::default()
+        };
+        let _ = ctx.run(raw_input, |ctx| {
+            App::apply_key_input(ctx, &KeyBindings::default(), &mut input);
+        });
+
+        assert!(input.brake.active);
+        assert!(!input.boost.active);
+    }
+
+    #[test]
+    fn test_motor_button_toggles_motor() {
+        let ctx = egui::Context::default();
+        let mut input = Level::simple_polygon(6).input;
+        assert!(input.motor.active);
+
+        // Runs a frame with the button, returning where it was drawn
+        let frame = |events: Vec<egui::Event>, input: &mut InputSet| {
+            let raw_input = egui::RawInput {
+                events,
+                ..Default::default()
+            };
+            let mut button_rect = egui::Rect::NOTHING;
+            let _ = ctx.run(raw_input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    button_rect = App::motor_button(ui, &mut input.motor).rect;
+                });
+            });
+            button_rect
+        };
+
+        let pos = frame(Vec::new(), &mut input).center();
+        let click = |pressed| egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        frame(
+            vec![egui::Event::PointerMoved(pos), click(true)],
+            &mut input,
+        );
+        frame(vec![click(false)], &mut input);
+        assert!(!input.motor.active);
+
+        // With the motor off, the body only slows down under friction
+        let mut coasting = Level::simple_polygon(6).body;
+        let mut powered = coasting.clone();
+        coasting.update(input, 1e-3);
+        input.motor.active = true;
+        powered.update(input, 1e-3);
+        assert!(powered.angular_velocity > coasting.angular_velocity);
+    }
+
+    #[test]
+    fn test_frame_time_histogram() {
+        let start = web_time::Instant::now();
+        let frame_times: Vec<_> = [0, 16, 33, 50, 250]
+            .into_iter()
+            .map(|millis| start + web_time::Duration::from_millis(millis))
+            .collect();
+
+        let histogram = App::frame_time_histogram(&frame_times);
+        assert_eq!(histogram[3], 3);
+        assert_eq!(histogram[HISTOGRAM_BUCKETS - 1], 1);
+        assert_eq!(histogram.iter().sum::<u32>(), 4);
+    }
+
+    #[test]
+    fn test_countdown_label() {
+        let at = |millis| App::countdown_label(web_time::Duration::from_millis(millis));
+
+        assert_eq!(at(0), Some("3"));
+        assert_eq!(at(1500), Some("2"));
+        assert_eq!(at(2999), Some("1"));
+        assert_eq!(at(3200), Some("GO!"));
+        assert_eq!(at(3500), None);
+    }
+
+    #[test]
+    fn test_intro_zoom() {
+        let at = |millis| App::intro_zoom(web_time::Duration::from_millis(millis));
+
+        assert!((at(0) - 0.1).abs() < 1e-6);
+        assert!((at(250) - 0.325).abs() < 1e-6);
+        assert_eq!(at(500), 1.0);
+        assert_eq!(at(800), 1.0);
+    }
+
+    #[test]
+    fn test_repaint_interval() {
+        let interval = App::repaint_interval(30.0);
+        assert!((interval.as_secs_f32() - 0.0333).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_format_playtime() {
+        assert_eq!(App::format_playtime(0.0), "0:00:00");
+        assert_eq!(App::format_playtime(59.9), "0:00:59");
+        assert_eq!(App::format_playtime(3725.0), "1:02:05");
+        assert_eq!(App::format_playtime(36000.0), "10:00:00");
+    }
+
+    #[test]
+    fn test_screenshot_filename() {
+        let now = web_time::UNIX_EPOCH + web_time::Duration::from_secs(1_709_210_096);
+        assert_eq!(
+            App::screenshot_filename(now),
+            "screenshot_20240229_123456.png"
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_save_screenshot() {
+        let path = std::env::temp_dir().join(format!("bouncy-{}.png", uuid::Uuid::new_v4()));
+        let image = egui::ColorImage::new([4, 3], Color32::RED);
+
+        App::save_screenshot(&image, path.to_str().unwrap()).unwrap();
+        let size = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+        assert!(size > 0);
     }
 }
@@ -1,13 +1,36 @@
-use egui::{emath::TSTransform, Pos2, Vec2};
+use egui::{emath::TSTransform, Color32, Pos2, Vec2};
+use ringbuffer::RingBuffer;
 use serde::{Deserialize, Serialize};
 
 use crate::drawable::Drawable;
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub const TRAIL_LENGTH: usize = 30;
+
+fn new_trail() -> ringbuffer::AllocRingBuffer<Pos2> {
+    ringbuffer::AllocRingBuffer::new(TRAIL_LENGTH)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Ball {
     pub center: Pos2,
     pub radius: f32,
     pub velocity: Vec2,
+    /// Fill colour, or the theme's error colour if unset
+    #[serde(default)]
+    pub color: Option<Color32>,
+    /// Recent positions, oldest first
+    #[serde(skip, default = "new_trail")]
+    pub trail: ringbuffer::AllocRingBuffer<Pos2>,
+}
+
+// The trail is only for show, so two balls in the same state are equal whatever their trails
+impl PartialEq for Ball {
+    fn eq(&self, other: &Self) -> bool {
+        self.center == other.center
+            && self.radius == other.radius
+            && self.velocity == other.velocity
+            && self.color == other.color
+    }
 }
 
 impl Default for Ball {
@@ -16,15 +39,39 @@ impl Default for Ball {
             center: Pos2::new(0.0, 0.0),
             radius: 0.05,
             velocity: Vec2::new(0.0, 0.0),
+            color: None,
+            trail: new_trail(),
         }
     }
 }
 
 impl Ball {
-    pub fn update(&mut self, dt: f32, gravity: f32) {
-        self.velocity.y += gravity * dt;
+    pub fn update(&mut self, dt: f32, gravity: Vec2) {
+        self.velocity += gravity * dt;
         self.center += self.velocity * dt;
     }
+
+    /// Draws the trail as circles that shrink and fade with age
+    pub fn draw_trail(&self, painter: &egui::Painter, transform: TSTransform, colour: Color32) {
+        for (i, position) in self.trail.iter().enumerate() {
+            let freshness = (i + 1) as f32 / TRAIL_LENGTH as f32;
+            let radius = self.radius * 0.6 * freshness * transform.scaling;
+            painter.circle_filled(
+                transform.mul_pos(*position),
+                radius,
+                colour.gamma_multiply(freshness),
+            );
+        }
+    }
+
+    /// Draws the ball as a translucent white circle, e.g. for a ghost from a previous run
+    pub fn draw_ghost(&self, painter: &egui::Painter, transform: TSTransform, opacity: f32) {
+        let center = transform.mul_pos(self.center);
+        let radius = self.radius * transform.scaling;
+
+        let fill = egui::Color32::from_white_alpha((255. * opacity) as u8);
+        painter.add(egui::Shape::circle_filled(center, radius, fill));
+    }
 }
 
 impl Drawable for Ball {
@@ -32,7 +79,84 @@ impl Drawable for Ball {
         let center = transform.mul_pos(self.center);
         let radius = self.radius * transform.scaling;
 
-        let fill = ctx.style().visuals.error_fg_color;
+        let fill = self.color.unwrap_or(ctx.style().visuals.error_fg_color);
         painter.add(egui::Shape::circle_filled(center, radius, fill));
     }
+
+    fn draw_to_svg(&self) -> String {
+        let fill = self.color.map_or("red".to_owned(), |colour| {
+            format!("#{:02x}{:02x}{:02x}", colour.r(), colour.g(), colour.b())
+        });
+        format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"none\"/>\n",
+            self.center.x, self.center.y, self.radius, fill
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1e-3;
+
+    fn ball(velocity: Vec2) -> Ball {
+        Ball {
+            velocity,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_moves_in_straight_line_without_gravity() {
+        let mut ball = ball(Vec2::new(1.0, -2.0));
+        for _ in 0..1000 {
+            ball.update(DT, Vec2::ZERO);
+        }
+
+        assert_eq!(ball.velocity, Vec2::new(1.0, -2.0));
+        assert!((ball.center - Pos2::new(1.0, -2.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_gravity_accelerates_ball() {
+        let gravity = Vec2::new(0.0, 9.81);
+        let mut ball = ball(Vec2::ZERO);
+
+        ball.update(DT, gravity);
+        assert_eq!(ball.velocity, gravity * DT);
+        assert!(ball.center.y > 0.0);
+        assert_eq!(ball.center.x, 0.0);
+    }
+
+    #[test]
+    fn test_large_time_step() {
+        let mut ball = ball(Vec2::new(3.0, 0.0));
+        ball.update(1.0, Vec2::new(0.0, 9.81));
+
+        assert_eq!(ball.velocity, Vec2::new(3.0, 9.81));
+        assert_eq!(ball.center, Pos2::new(3.0, 9.81));
+    }
+
+    #[test]
+    fn test_stays_finite_over_many_ticks() {
+        let mut ball = ball(Vec2::new(0.5, -1.0));
+        for _ in 0..10_000 {
+            ball.update(DT, Vec2::new(0.0, 9.81));
+        }
+
+        assert!(ball.center.is_finite());
+        assert!(ball.velocity.is_finite());
+        assert!((ball.velocity.y - (-1.0 + 9.81 * 10.0)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_draw_to_svg() {
+        let ball = Ball {
+            radius: 0.125,
+            ..Default::default()
+        };
+
+        assert!(ball.draw_to_svg().contains(" r=\"0.125\""));
+    }
 }
@@ -0,0 +1,997 @@
+use std::path::{Path, PathBuf};
+
+use web_time::{Duration, Instant};
+
+use bouncy::{
+    drawable::Drawable,
+    level::{self, Level},
+    shape::Shape,
+};
+use egui::{emath::TSTransform, Pos2, Rect};
+
+use crate::{
+    canvas::{self, MirrorAxis, VertexIndex},
+    file,
+    history::History,
+    library::{self, Preset},
+    playtest::{TestPlay, TestResult},
+};
+
+const WIP_KEY: &str = "builder_wip";
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct LevelBuilder {
+    pub level: Level,
+    /// JSON pasted in to be loaded
+    json_text: String,
+    status: Option<String>,
+    snap_to_grid: bool,
+    grid_size: f32,
+    drag: Option<VertexDrag>,
+    context_target: Option<ContextTarget>,
+    history: History,
+    presets: Vec<Preset>,
+    test_play: Option<TestPlay>,
+    test_result: Option<TestResult>,
+    /// File the level is saved to and loaded from
+    path: String,
+    /// The level as it was last saved or loaded, to detect unsaved changes
+    saved_json: Option<String>,
+    confirm_load: bool,
+    /// Work in progress left over from the last session, waiting to be restored or discarded
+    unrestored_wip: Option<Level>,
+    last_autosave: Instant,
+}
+
+/// What a right click in the canvas landed on
+#[derive(Clone, Copy)]
+enum ContextTarget {
+    Vertex(VertexIndex),
+    Segment(VertexIndex),
+}
+
+/// Vertices being dragged, along with the view they were grabbed in. The view is held fixed for
+/// the drag, since moving a vertex can change the extent of the shape.
+struct VertexDrag {
+    vertices: Vec<VertexIndex>,
+    transform: TSTransform,
+    /// The shape before the drag, recorded in the history once the drag ends if it changed
+    before: Shape,
+}
+
+impl Default for LevelBuilder {
+    fn default() -> Self {
+        let mut builder = Self {
+            level: Level::simple_polygon(6),
+            json_text: String::new(),
+            status: None,
+            snap_to_grid: false,
+            grid_size: 0.1,
+            drag: None,
+            context_target: None,
+            history: History::default(),
+            presets: library::presets(),
+            test_play: None,
+            test_result: None,
+            path: "level.json".to_owned(),
+            saved_json: None,
+            confirm_load: false,
+            unrestored_wip: None,
+            last_autosave: Instant::now(),
+        };
+        builder.mark_saved();
+        builder
+    }
+}
+
+impl LevelBuilder {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self {
+            unrestored_wip: cc.storage.and_then(load_wip),
+            ..Default::default()
+        }
+    }
+
+    fn store_wip(&self, storage: &mut dyn eframe::Storage) {
+        if let Ok(json) = serde_json::to_string(&self.level) {
+            storage.set_string(WIP_KEY, json);
+        }
+    }
+
+    fn autosave(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let since_autosave = self.last_autosave.elapsed();
+        if since_autosave < AUTOSAVE_INTERVAL {
+            ctx.request_repaint_after(AUTOSAVE_INTERVAL - since_autosave);
+            return;
+        }
+
+        // Don't overwrite the previous session's work before the user has decided what to do
+        if self.unrestored_wip.is_none() {
+            if let Some(storage) = frame.storage_mut() {
+                self.store_wip(storage);
+                storage.flush();
+            }
+        }
+        self.last_autosave = Instant::now();
+        ctx.request_repaint_after(AUTOSAVE_INTERVAL);
+    }
+
+    fn restore_bar(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if self.unrestored_wip.is_none() {
+            return;
+        }
+
+        egui::TopBottomPanel::top("restore").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Unsaved work found — Restore?");
+                if ui.button("Restore").clicked() {
+                    if let Some(level) = self.unrestored_wip.take() {
+                        self.replace_level(level);
+                    }
+                }
+                if ui.button("Discard").clicked() {
+                    self.unrestored_wip = None;
+                    if let Some(storage) = frame.storage_mut() {
+                        storage.set_string(WIP_KEY, String::new());
+                    }
+                }
+            });
+        });
+    }
+
+    /// Fits the whole body into the canvas, leaving a margin
+    pub fn view_transform(&self, canvas_rect: Rect) -> TSTransform {
+        let body = &self.level.body;
+        let radius = body
+            .shape
+            .all_points()
+            .iter()
+            .map(|point| (*point - body.center_of_rotation).length())
+            .fold(0.5, f32::max);
+
+        TSTransform {
+            scaling: 0.8 * canvas_rect.size().min_elem() / (2. * radius),
+            translation: canvas_rect.center().to_vec2(),
+        }
+    }
+
+    fn snap(&self, pos: Pos2) -> Pos2 {
+        if self.snap_to_grid {
+            canvas::snap_to_grid(pos, self.grid_size)
+        } else {
+            pos
+        }
+    }
+
+    fn library_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Library");
+
+        let mut chosen = None;
+        ui.horizontal_wrapped(|ui| {
+            for (idx, preset) in self.presets.iter().enumerate() {
+                let (rect, response) =
+                    ui.allocate_exact_size(egui::vec2(48., 48.), egui::Sense::click());
+                let painter = ui.painter_at(rect);
+
+                if response.hovered() {
+                    painter.rect_filled(rect, 4., ui.visuals().widgets.hovered.weak_bg_fill);
+                }
+
+                let radius = preset
+                    .shape
+                    .all_points()
+                    .iter()
+                    .map(|point| point.to_vec2().length())
+                    .fold(0.5, f32::max);
+                let transform = TSTransform {
+                    scaling: 0.4 * rect.width() / radius,
+                    translation: rect.center().to_vec2(),
+                };
+                preset.shape.draw(ui.ctx(), &painter, transform);
+
+                if response.on_hover_text(&preset.name).clicked() {
+                    chosen = Some(idx);
+                }
+            }
+        });
+
+        if let Some(idx) = chosen {
+            self.end_drag();
+            let body = &mut self.level.body;
+            self.history.record(body.shape.clone());
+            body.shape = self.presets[idx]
+                .shape
+                .translate(body.center_of_rotation.to_vec2());
+        }
+    }
+
+    fn shape_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Shape");
+
+        let shape = &self.level.body.shape;
+        if !shape.has_closing_gap()
+            && shape.openings().is_empty()
+            && self.level.escape_zones.is_empty()
+        {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "⚠ The shape is closed, so the ball can't escape",
+            );
+        }
+
+        let before = self.level.body.shape.clone();
+        let mut edited = false;
+        let lines = &mut self.level.body.shape.lines;
+        let mut remove_line = None;
+        for (line_idx, line) in lines.iter_mut().enumerate() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(format!("Line {}", line_idx + 1));
+                if ui.small_button("🗑").clicked() {
+                    remove_line = Some(line_idx);
+                }
+            });
+
+            let mut action = None;
+            let len = line.len();
+            for (point_idx, point) in line.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    let x = ui.add(egui::DragValue::new(&mut point.x).speed(0.01).prefix("x: "));
+                    let y = ui.add(egui::DragValue::new(&mut point.y).speed(0.01).prefix("y: "));
+                    edited |= starts_edit(&x) || starts_edit(&y);
+                    if ui
+                        .add_enabled(point_idx > 0, egui::Button::new("⏶").small())
+                        .clicked()
+                    {
+                        action = Some(VertexAction::Swap(point_idx - 1, point_idx));
+                    }
+                    if ui
+                        .add_enabled(point_idx + 1 < len, egui::Button::new("⏷").small())
+                        .clicked()
+                    {
+                        action = Some(VertexAction::Swap(point_idx, point_idx + 1));
+                    }
+                    if ui.small_button("🗑").clicked() {
+                        action = Some(VertexAction::Remove(point_idx));
+                    }
+                });
+            }
+
+            edited |= action.is_some();
+            match action {
+                Some(VertexAction::Swap(a, b)) => line.swap(a, b),
+                Some(VertexAction::Remove(idx)) => {
+                    line.remove(idx);
+                }
+                None => {}
+            }
+
+            if ui.button("Add vertex").clicked() {
+                edited = true;
+                let last = line.last().copied().unwrap_or(Pos2::ZERO);
+                line.push(last + egui::vec2(0.1, 0.0));
+            }
+        }
+
+        if let Some(line_idx) = remove_line {
+            edited = true;
+            lines.remove(line_idx);
+        }
+
+        ui.separator();
+        if ui.button("Add line").clicked() {
+            edited = true;
+            lines.push(vec![Pos2::new(0.0, 0.0), Pos2::new(0.5, 0.0)]);
+        }
+
+        if edited {
+            self.history.record(before);
+        }
+    }
+
+    fn parameters_panel(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Level");
+            if ui.button("Reset to Defaults").clicked() {
+                reset_parameters(&mut self.level);
+            }
+        });
+
+        let level = &mut self.level;
+        egui::Grid::new("parameters").num_columns(2).show(ui, |ui| {
+            ui.label("Name");
+            if ui.text_edit_singleline(&mut level.name).changed() {
+                level.id_name = level::id_name_from(&level.name);
+            }
+            ui.end_row();
+
+            ui.label("Gravity");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut level.gravity.x).speed(0.1));
+                ui.add(egui::DragValue::new(&mut level.gravity.y).speed(0.1));
+            });
+            ui.end_row();
+
+            ui.label("Max time (s)");
+            let mut max_time = level.max_time.as_secs_f32();
+            if ui
+                .add(
+                    egui::DragValue::new(&mut max_time)
+                        .speed(0.5)
+                        .range(1.0..=600.0),
+                )
+                .changed()
+            {
+                level.max_time = web_time::Duration::from_secs_f32(max_time);
+            }
+            ui.end_row();
+
+            ui.label("Max brake work");
+            ui.add(egui::DragValue::new(&mut level.max_brake_work).range(0.0..=f32::MAX));
+            ui.end_row();
+
+            ui.label("Max boost work");
+            ui.add(egui::DragValue::new(&mut level.max_boost_work).range(0.0..=f32::MAX));
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.heading("Inputs");
+        egui::Grid::new("inputs").num_columns(2).show(ui, |ui| {
+            let torque = |value| egui::DragValue::new(value).speed(0.01).fixed_decimals(2);
+
+            ui.label("Brake torque");
+            ui.add(torque(&mut level.input.brake.torque));
+            ui.end_row();
+
+            ui.label("Boost torque");
+            ui.add(torque(&mut level.input.boost.torque));
+            ui.end_row();
+
+            ui.label("Motor torque");
+            ui.add(torque(&mut level.input.motor.torque));
+            ui.end_row();
+
+            ui.label("Motor active");
+            ui.checkbox(&mut level.input.motor.active, "");
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.heading("Body");
+        let body = &mut level.body;
+        egui::Grid::new("body").num_columns(2).show(ui, |ui| {
+            ui.label("Friction coefficient");
+            ui.add(
+                egui::DragValue::new(&mut body.friction_coefficient)
+                    .speed(0.01)
+                    .range(0.0..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Angular velocity");
+            ui.add(egui::DragValue::new(&mut body.angular_velocity).speed(0.01));
+            ui.end_row();
+
+            ui.label("Moment of inertia");
+            ui.add(
+                egui::DragValue::new(&mut body.moment_of_inertia)
+                    .speed(0.01)
+                    .range(0.01..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Initial angle");
+            ui.drag_angle(&mut level.initial_body_angle);
+            ui.end_row();
+
+            ui.label("Colour");
+            ui.horizontal(|ui| {
+                let mut custom = body.color.is_some();
+                if ui.checkbox(&mut custom, "").changed() {
+                    body.color = custom.then(|| ui.visuals().text_color());
+                }
+                if let Some(colour) = &mut body.color {
+                    ui.color_edit_button_srgba(colour);
+                }
+            });
+            ui.end_row();
+        });
+
+        ui.separator();
+        egui::Grid::new("launch").num_columns(2).show(ui, |ui| {
+            ui.label("Initial Vx");
+            ui.add(egui::Slider::new(
+                &mut level.ball_initial_velocity.x,
+                -5.0..=5.0,
+            ));
+            ui.end_row();
+
+            ui.label("Initial Vy");
+            ui.add(egui::Slider::new(
+                &mut level.ball_initial_velocity.y,
+                -5.0..=5.0,
+            ));
+            ui.end_row();
+
+            ui.label("Ball colour");
+            ui.horizontal(|ui| {
+                let mut custom = level.ball_color.is_some();
+                if ui.checkbox(&mut custom, "").changed() {
+                    level.ball_color = custom.then(|| ui.visuals().error_fg_color);
+                }
+                if let Some(colour) = &mut level.ball_color {
+                    ui.color_edit_button_srgba(colour);
+                }
+            });
+            ui.end_row();
+        });
+
+        for (idx, ball) in level.balls.iter_mut().enumerate() {
+            ui.separator();
+            ui.heading(format!("Ball {}", idx + 1));
+            egui::Grid::new(("ball", idx))
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Radius");
+                    ui.add(
+                        egui::DragValue::new(&mut ball.radius)
+                            .speed(0.005)
+                            .range(0.005..=1.0),
+                    );
+                    ui.end_row();
+
+                    ui.label("Center");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut ball.center.x).speed(0.01));
+                        ui.add(egui::DragValue::new(&mut ball.center.y).speed(0.01));
+                    });
+                    ui.end_row();
+                });
+        }
+    }
+
+    fn undo(&mut self) {
+        self.end_drag();
+        self.history.undo(&mut self.level.body.shape);
+    }
+
+    fn redo(&mut self) {
+        self.end_drag();
+        self.history.redo(&mut self.level.body.shape);
+    }
+
+    /// Finishes any drag in progress, adding it to the history only if it moved something
+    fn end_drag(&mut self) {
+        if let Some(drag) = self.drag.take() {
+            if drag.before != self.level.body.shape {
+                self.history.record(drag.before);
+            }
+        }
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        let redo_shift_z = egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::Z,
+        );
+        let redo_y = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
+        let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+
+        // Check the shifted shortcut first, since Ctrl+Z also matches Ctrl+Shift+Z
+        if ctx.input_mut(|i| i.consume_shortcut(&redo_shift_z) || i.consume_shortcut(&redo_y)) {
+            self.redo();
+        } else if ctx.input_mut(|i| i.consume_shortcut(&undo)) {
+            self.undo();
+        }
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis) {
+        self.end_drag();
+        let body = &mut self.level.body;
+        self.history.record(body.shape.clone());
+        canvas::mirror(&mut body.shape, axis, body.center_of_rotation);
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("▶ Test").clicked() {
+                // Play a copy so nothing that happens in the game leaks back into the editor
+                self.end_drag();
+                self.test_play = Some(TestPlay::new(self.level.clone()));
+            }
+
+            ui.separator();
+            if ui
+                .add_enabled(self.history.can_undo(), egui::Button::new("⟲ Undo"))
+                .clicked()
+            {
+                self.undo();
+            }
+            if ui
+                .add_enabled(self.history.can_redo(), egui::Button::new("⟳ Redo"))
+                .clicked()
+            {
+                self.redo();
+            }
+
+            ui.separator();
+            if ui.button("Mirror H").clicked() {
+                self.mirror(MirrorAxis::Horizontal);
+            }
+            if ui.button("Mirror V").clicked() {
+                self.mirror(MirrorAxis::Vertical);
+            }
+
+            ui.separator();
+            ui.checkbox(&mut self.snap_to_grid, "Snap to grid");
+            ui.add_enabled(
+                self.snap_to_grid,
+                egui::DragValue::new(&mut self.grid_size)
+                    .speed(0.01)
+                    .range(0.01..=1.0)
+                    .prefix("Grid: "),
+            );
+        });
+    }
+
+    fn draw_grid(&self, painter: &egui::Painter, transform: TSTransform, rect: Rect) {
+        let visible = transform.inverse().mul_rect(rect);
+        let (min, max) = (
+            (visible.min.to_vec2() / self.grid_size).ceil(),
+            (visible.max.to_vec2() / self.grid_size).floor(),
+        );
+
+        // Too many dots to be useful, and too many to draw
+        if (max.x - min.x) * (max.y - min.y) > 20_000. {
+            return;
+        }
+
+        let colour = painter
+            .ctx()
+            .style()
+            .visuals
+            .weak_text_color()
+            .gamma_multiply(0.4);
+        for i in min.x as i32..=max.x as i32 {
+            for j in min.y as i32..=max.y as i32 {
+                let point = Pos2::new(i as f32, j as f32) * self.grid_size;
+                painter.circle_filled(transform.mul_pos(point), 1.0, colour);
+            }
+        }
+    }
+
+    fn test_result_window(&mut self, ctx: &egui::Context) {
+        let Some(result) = &self.test_result else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new("Last test")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} after {:.1}s", result.outcome, result.elapsed));
+                egui::Grid::new("test_stats").striped(true).show(ui, |ui| {
+                    let stats = &result.stats;
+                    ui.label("Collisions");
+                    ui.label(stats.total_collisions.to_string());
+                    ui.end_row();
+
+                    ui.label("Distance traveled");
+                    ui.label(format!("{:.2}", stats.distance_traveled));
+                    ui.end_row();
+
+                    ui.label("Max speed");
+                    ui.label(format!("{:.2}", stats.max_speed));
+                    ui.end_row();
+
+                    ui.label("Brake work");
+                    ui.label(format!("{:.1}", stats.work_spent_brake));
+                    ui.end_row();
+
+                    ui.label("Boost work");
+                    ui.label(format!("{:.1}", stats.work_spent_boost));
+                    ui.end_row();
+                });
+            });
+
+        if !open {
+            self.test_result = None;
+        }
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        serde_json::to_string(&self.level).ok() != self.saved_json
+    }
+
+    fn mark_saved(&mut self) {
+        self.saved_json = serde_json::to_string(&self.level).ok();
+    }
+
+    fn replace_level(&mut self, level: Level) {
+        self.level = level;
+        self.drag = None;
+        self.history = History::default();
+        self.mark_saved();
+        self.status = Some(format!("Loaded \"{}\"", self.level.name));
+    }
+
+    fn save_file(&mut self) {
+        let path = PathBuf::from(&self.path);
+        match file::save(&path, &self.level) {
+            Ok(()) => {
+                self.mark_saved();
+                self.status = Some(format!("Saved to {}", path.display()));
+            }
+            Err(err) => self.status = Some(format!("Failed to save: {err}")),
+        }
+    }
+
+    fn export_svg(&mut self) {
+        let path = PathBuf::from(&self.path).with_extension("svg");
+        match file::export_svg(&path, &self.level) {
+            Ok(()) => self.status = Some(format!("Exported to {}", path.display())),
+            Err(err) => self.status = Some(format!("Failed to export: {err}")),
+        }
+    }
+
+    fn load_file(&mut self) {
+        match file::load(Path::new(&self.path)) {
+            Ok(level) => self.replace_level(level),
+            Err(err) => self.status = Some(format!("Failed to load: {err}")),
+        }
+    }
+
+    fn confirm_load_window(&mut self, ctx: &egui::Context) {
+        if !self.confirm_load {
+            return;
+        }
+
+        egui::Window::new("Load level")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Unsaved changes will be lost");
+                ui.horizontal(|ui| {
+                    if ui.button("Load anyway").clicked() {
+                        self.confirm_load = false;
+                        self.load_file();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_load = false;
+                    }
+                });
+            });
+    }
+
+    fn file_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("File:");
+            ui.text_edit_singleline(&mut self.path);
+
+            if ui.button("💾 Save").clicked() {
+                self.save_file();
+            }
+
+            if ui.button("📂 Load").clicked() {
+                if self.has_unsaved_changes() {
+                    self.confirm_load = true;
+                } else {
+                    self.load_file();
+                }
+            }
+
+            if ui.button("🖼 Export SVG").clicked() {
+                self.export_svg();
+            }
+
+            ui.separator();
+
+            if ui.button("Save to JSON").clicked() {
+                println!("{}", file::to_json(&self.level));
+                self.status = Some("Level bundle written to stdout".to_owned());
+            }
+
+            if ui.button("Load from JSON").clicked() {
+                match file::from_json(&self.json_text) {
+                    Ok(level) => self.replace_level(level),
+                    Err(err) => self.status = Some(format!("Failed to load: {err}")),
+                }
+            }
+
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+        });
+
+        ui.add(
+            egui::TextEdit::multiline(&mut self.json_text)
+                .hint_text("Paste level JSON here to load it")
+                .desired_rows(3)
+                .desired_width(f32::INFINITY)
+                .code_editor(),
+        );
+    }
+    fn context_menu(&mut self, ui: &mut egui::Ui) {
+        let shape = &mut self.level.body.shape;
+        match self.context_target {
+            Some(ContextTarget::Vertex(index)) => {
+                let can_delete = canvas::vertex_count(shape, index.0) > 3;
+                if ui
+                    .add_enabled(can_delete, egui::Button::new("Delete Vertex"))
+                    .clicked()
+                {
+                    self.history.record(shape.clone());
+                    canvas::delete_vertex(shape, index);
+                    ui.close_menu();
+                }
+            }
+            Some(ContextTarget::Segment(segment)) => {
+                if ui.button("Split").clicked() {
+                    let line = &shape.lines[segment.0];
+                    let midpoint = line[segment.1].lerp(line[segment.1 + 1], 0.5);
+                    self.history.record(shape.clone());
+                    canvas::insert_vertex(shape, segment, midpoint);
+                    ui.close_menu();
+                }
+                if ui.button("Delete Segment").clicked() {
+                    self.history.record(shape.clone());
+                    canvas::delete_segment(shape, segment);
+                    ui.close_menu();
+                }
+            }
+            None => {
+                ui.close_menu();
+            }
+        }
+    }
+
+    fn canvas(&mut self, ui: &mut egui::Ui) {
+        let (response, painter) =
+            ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+        let transform = match &self.drag {
+            Some(drag) => drag.transform,
+            None => self.view_transform(response.rect),
+        };
+        let threshold = 10. / transform.scaling;
+        let pointer = response
+            .hover_pos()
+            .map(|pos| transform.inverse().mul_pos(pos));
+
+        let shape = &self.level.body.shape;
+        let hovered = match &self.drag {
+            Some(drag) => drag.vertices.first().copied(),
+            None => pointer.and_then(|pos| canvas::closest_vertex(shape, pos, threshold)),
+        };
+        let hovered_segment = match hovered {
+            Some(_) => None,
+            None => pointer.and_then(|pos| canvas::closest_segment(shape, pos, threshold)),
+        };
+
+        let pressed = response.drag_started_by(egui::PointerButton::Primary)
+            || response.clicked_by(egui::PointerButton::Primary);
+        if pressed && self.drag.is_none() {
+            let before = self.level.body.shape.clone();
+            let grabbed = match (hovered, hovered_segment, pointer) {
+                (Some(index), _, _) => Some(index),
+                (None, Some(segment), Some(pos)) => {
+                    let pos = self.snap(pos);
+                    Some(canvas::insert_vertex(
+                        &mut self.level.body.shape,
+                        segment,
+                        pos,
+                    ))
+                }
+                _ => None,
+            };
+
+            if let Some(index) = grabbed {
+                self.drag = Some(VertexDrag {
+                    vertices: canvas::coincident_vertices(&self.level.body.shape, index),
+                    transform,
+                    before,
+                });
+            }
+        }
+
+        if response.secondary_clicked() {
+            self.context_target = match (hovered, hovered_segment) {
+                (Some(index), _) => Some(ContextTarget::Vertex(index)),
+                (None, Some(segment)) => Some(ContextTarget::Segment(segment)),
+                _ => None,
+            };
+        }
+        if self.context_target.is_some() {
+            response.context_menu(|ui| self.context_menu(ui));
+        }
+
+        if response.dragged_by(egui::PointerButton::Primary) {
+            if let (Some(drag), Some(pos)) = (&self.drag, ui.ctx().pointer_latest_pos()) {
+                let pos = self.snap(transform.inverse().mul_pos(pos));
+                for (line_idx, point_idx) in &drag.vertices {
+                    self.level.body.shape.lines[*line_idx][*point_idx] = pos;
+                }
+            }
+        }
+
+        if response.drag_stopped() || !response.is_pointer_button_down_on() {
+            self.end_drag();
+        }
+
+        if self.snap_to_grid {
+            self.draw_grid(&painter, transform, response.rect);
+        }
+
+        let ctx = ui.ctx();
+        let body = &self.level.body;
+        if self.level.initial_body_angle != 0.0 {
+            let start = body
+                .shape
+                .rotate(self.level.initial_body_angle, body.center_of_rotation);
+            start.draw_with_color(&painter, transform, ui.visuals().weak_text_color());
+        }
+        body.draw(ctx, &painter, transform);
+        for ball in &self.level.balls {
+            // Show the level-wide colour the game will give balls without their own
+            let mut ball = ball.clone();
+            ball.color = ball.color.or(self.level.ball_color);
+            ball.draw(ctx, &painter, transform);
+        }
+
+        if let Some((line_idx, point_idx)) = hovered_segment {
+            let line = &self.level.body.shape.lines[line_idx];
+            let segment = [line[point_idx], line[point_idx + 1]].map(|p| transform.mul_pos(p));
+            painter.line_segment(segment, ui.visuals().selection.stroke);
+        }
+
+        if let Some((line_idx, point_idx)) = hovered {
+            let point = self.level.body.shape.lines[line_idx][point_idx];
+            painter.circle_stroke(
+                transform.mul_pos(point),
+                6.,
+                ui.visuals().widgets.hovered.fg_stroke,
+            );
+        }
+    }
+}
+
+fn load_wip(storage: &dyn eframe::Storage) -> Option<Level> {
+    let json = storage.get_string(WIP_KEY)?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Restores everything but the shape and identity of the level to the defaults
+fn reset_parameters(level: &mut Level) {
+    let mut defaults = Level::simple_polygon(6);
+    defaults.id = level.id;
+    defaults.name = std::mem::take(&mut level.name);
+    defaults.id_name = std::mem::take(&mut level.id_name);
+    defaults.body.shape = level.body.shape.clone();
+    defaults.body.center_of_rotation = level.body.center_of_rotation;
+    *level = defaults;
+}
+
+/// Whether a numeric field has just started being changed, by dragging or typing
+fn starts_edit(response: &egui::Response) -> bool {
+    response.drag_started() || response.gained_focus()
+}
+
+enum VertexAction {
+    Swap(usize, usize),
+    Remove(usize),
+}
+
+impl eframe::App for LevelBuilder {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if self.unrestored_wip.is_none() {
+            self.store_wip(storage);
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.autosave(ctx, frame);
+
+        if let Some(test_play) = &mut self.test_play {
+            if let Some(result) = test_play.show(ctx) {
+                self.test_play = None;
+                self.test_result = Some(result);
+            }
+            return;
+        }
+
+        self.handle_shortcuts(ctx);
+        self.restore_bar(ctx, frame);
+        self.test_result_window(ctx);
+        self.confirm_load_window(ctx);
+
+        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| self.toolbar(ui));
+        egui::TopBottomPanel::bottom("file").show(ctx, |ui| self.file_bar(ui));
+
+        egui::SidePanel::left("library")
+            .resizable(true)
+            .default_width(120.)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.library_panel(ui));
+            });
+
+        egui::SidePanel::left("shape")
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.shape_panel(ui));
+            });
+
+        egui::SidePanel::right("parameters")
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.parameters_panel(ui));
+            });
+
+        egui::CentralPanel::default().show(ctx, |ui| self.canvas(ui));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn test_drag_records_history_only_when_moved() {
+        let mut builder = LevelBuilder::default();
+        let grab = |builder: &mut LevelBuilder| {
+            builder.drag = Some(VertexDrag {
+                vertices: vec![(0, 1)],
+                transform: TSTransform::IDENTITY,
+                before: builder.level.body.shape.clone(),
+            });
+        };
+
+        // A click on a vertex without moving it
+        grab(&mut builder);
+        builder.end_drag();
+        assert!(!builder.history.can_undo());
+
+        grab(&mut builder);
+        builder.level.body.shape.lines[0][1] = Pos2::new(0.3, 0.4);
+        builder.end_drag();
+        assert!(builder.history.can_undo());
+    }
+
+    #[test]
+    fn test_save_stores_wip() {
+        let mut builder = LevelBuilder::default();
+        builder.level.name = "Work in progress".to_owned();
+        builder.level.body.shape.lines[0][1] = Pos2::new(0.3, 0.4);
+
+        let mut storage = MemoryStorage::default();
+        eframe::App::save(&mut builder, &mut storage);
+
+        let json = storage.0.get(WIP_KEY).expect("No work in progress stored");
+        assert!(serde_json::from_str::<serde_json::Value>(json).is_ok());
+
+        let restored = load_wip(&storage).unwrap();
+        assert_eq!(restored.name, "Work in progress");
+        assert_eq!(restored.body.shape.lines, builder.level.body.shape.lines);
+    }
+}
@@ -0,0 +1,241 @@
+use bouncy::shape::{self, Shape};
+use egui::Pos2;
+
+/// Position of a vertex in `Shape::lines`, as (line index, point index)
+pub type VertexIndex = (usize, usize);
+
+/// Closest vertex to `pos` that lies within `threshold`
+pub fn closest_vertex(shape: &Shape, pos: Pos2, threshold: f32) -> Option<VertexIndex> {
+    shape
+        .lines
+        .iter()
+        .enumerate()
+        .flat_map(|(line_idx, line)| {
+            line.iter()
+                .enumerate()
+                .map(move |(point_idx, point)| ((line_idx, point_idx), point.distance(pos)))
+        })
+        .filter(|(_, distance)| *distance < threshold)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+/// Closest segment to `pos` that lies within `threshold`, as (line index, index of its first point)
+pub fn closest_segment(shape: &Shape, pos: Pos2, threshold: f32) -> Option<VertexIndex> {
+    shape
+        .lines
+        .iter()
+        .enumerate()
+        .flat_map(|(line_idx, line)| {
+            line.windows(2).enumerate().map(move |(point_idx, w)| {
+                let distance = shape::distance_to_segment(pos, (w[0], w[1]));
+                ((line_idx, point_idx), distance)
+            })
+        })
+        .filter(|(_, distance)| *distance < threshold)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+/// Splits the segment starting at `segment` by inserting `pos`, returning the new vertex
+pub fn insert_vertex(shape: &mut Shape, segment: VertexIndex, pos: Pos2) -> VertexIndex {
+    let (line_idx, point_idx) = segment;
+    shape.lines[line_idx].insert(point_idx + 1, pos);
+    (line_idx, point_idx + 1)
+}
+
+fn is_closed(line: &[Pos2]) -> bool {
+    match (line.first(), line.last()) {
+        (Some(first), Some(last)) => {
+            line.len() > 1 && first.distance(*last) <= shape::CLOSING_EPSILON
+        }
+        _ => false,
+    }
+}
+
+/// Number of distinct vertices in the line, not counting the repeated end of a closed line
+pub fn vertex_count(shape: &Shape, line_idx: usize) -> usize {
+    let line = &shape.lines[line_idx];
+    line.len() - is_closed(line) as usize
+}
+
+/// Removes a vertex, keeping closed lines closed
+pub fn delete_vertex(shape: &mut Shape, index: VertexIndex) {
+    let (line_idx, point_idx) = index;
+    let line = &mut shape.lines[line_idx];
+
+    if is_closed(line) && (point_idx == 0 || point_idx == line.len() - 1) {
+        line.pop();
+        line.remove(0);
+        line.push(line[0]);
+    } else {
+        line.remove(point_idx);
+    }
+}
+
+/// Removes the segment starting at `segment`, leaving a gap. A closed line is opened up at the
+/// segment, otherwise the line is split in two.
+pub fn delete_segment(shape: &mut Shape, segment: VertexIndex) {
+    let (line_idx, point_idx) = segment;
+    let line = &shape.lines[line_idx];
+
+    let replacement = if is_closed(line) {
+        let mut opened = line[point_idx + 1..line.len() - 1].to_vec();
+        opened.extend_from_slice(&line[..=point_idx]);
+        vec![opened]
+    } else {
+        vec![line[..=point_idx].to_vec(), line[point_idx + 1..].to_vec()]
+    };
+
+    let replacement = replacement.into_iter().filter(|line| line.len() > 1);
+    shape.lines.splice(line_idx..=line_idx, replacement);
+}
+
+#[derive(Clone, Copy)]
+pub enum MirrorAxis {
+    /// Reflect left to right, through a vertical line
+    Horizontal,
+    /// Reflect top to bottom, through a horizontal line
+    Vertical,
+}
+
+/// Extends each line with its own reflection, traced backwards so the line doubles back on
+/// itself to form a symmetric shape
+pub fn mirror(shape: &mut Shape, axis: MirrorAxis, center: Pos2) {
+    let reflect = |point: Pos2| match axis {
+        MirrorAxis::Horizontal => Pos2::new(2. * center.x - point.x, point.y),
+        MirrorAxis::Vertical => Pos2::new(point.x, 2. * center.y - point.y),
+    };
+
+    for line in &mut shape.lines {
+        let reflected: Vec<Pos2> = line.iter().rev().map(|point| reflect(*point)).collect();
+        for point in reflected {
+            // Points on the axis reflect onto themselves, which would leave zero-length segments
+            if line
+                .last()
+                .is_some_and(|last| last.distance(point) <= shape::CLOSING_EPSILON)
+            {
+                continue;
+            }
+            line.push(point);
+        }
+    }
+}
+
+/// All vertices at the same position as `index`, so that closed lines stay closed when one end
+/// is moved
+pub fn coincident_vertices(shape: &Shape, index: VertexIndex) -> Vec<VertexIndex> {
+    let target = shape.lines[index.0][index.1];
+    shape
+        .lines
+        .iter()
+        .enumerate()
+        .flat_map(|(line_idx, line)| {
+            line.iter()
+                .enumerate()
+                .map(move |(point_idx, point)| ((line_idx, point_idx), *point))
+        })
+        .filter(|(_, point)| point.distance(target) <= shape::CLOSING_EPSILON)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+pub fn snap_to_grid(pos: Pos2, grid_size: f32) -> Pos2 {
+    if grid_size <= 0.0 {
+        return pos;
+    }
+    ((pos.to_vec2() / grid_size).round() * grid_size).to_pos2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_vertex() {
+        let shape = Shape::regular_polygon(4, 1.0, Pos2::ZERO);
+
+        assert_eq!(
+            closest_vertex(&shape, Pos2::new(0.05, 0.95), 0.1),
+            Some((0, 1))
+        );
+        assert_eq!(closest_vertex(&shape, Pos2::new(0.5, 0.5), 0.1), None);
+
+        // The first and last points of a closed polygon coincide
+        let closing = coincident_vertices(&shape, (0, 0));
+        assert_eq!(closing, vec![(0, 0), (0, 4)]);
+
+        assert_eq!(
+            snap_to_grid(Pos2::new(0.26, -0.34), 0.1),
+            Pos2::new(0.3, -0.3)
+        );
+    }
+
+    #[test]
+    fn test_insert_vertex() {
+        let mut shape = Shape::regular_polygon(4, 1.0, Pos2::ZERO);
+        let midpoint = shape.lines[0][1].lerp(shape.lines[0][2], 0.5);
+
+        assert_eq!(closest_vertex(&shape, midpoint, 0.1), None);
+        let segment = closest_segment(&shape, midpoint, 0.1).unwrap();
+        assert_eq!(segment, (0, 1));
+
+        let inserted = insert_vertex(&mut shape, segment, midpoint);
+        assert_eq!(inserted, (0, 2));
+        assert_eq!(shape.lines[0].len(), 6);
+        assert_eq!(shape.lines[0][2], midpoint);
+    }
+
+    #[test]
+    fn test_delete() {
+        let square = Shape::regular_polygon(4, 1.0, Pos2::ZERO);
+        assert_eq!(vertex_count(&square, 0), 4);
+
+        let mut shape = square.clone();
+        delete_vertex(&mut shape, (0, 0));
+        assert_eq!(vertex_count(&shape, 0), 3);
+        assert!(is_closed(&shape.lines[0]));
+        assert_eq!(shape.lines[0][0], square.lines[0][1]);
+
+        let mut shape = square.clone();
+        delete_segment(&mut shape, (0, 1));
+        assert_eq!(shape.lines.len(), 1);
+        assert_eq!(shape.lines[0].len(), 4);
+        assert_eq!(
+            shape.openings(),
+            vec![(square.lines[0][1], square.lines[0][2])]
+        );
+
+        // Deleting again splits the now open line in two
+        delete_segment(&mut shape, (0, 1));
+        assert_eq!(shape.lines.len(), 2);
+        assert_eq!(shape.openings().len(), 2);
+    }
+
+    #[test]
+    fn test_mirror() {
+        // Right half of a diamond, from top to bottom
+        let mut shape = Shape::new(vec![vec![
+            Pos2::new(0.0, -1.0),
+            Pos2::new(1.0, 0.0),
+            Pos2::new(0.0, 1.0),
+        ]]);
+
+        mirror(&mut shape, MirrorAxis::Horizontal, Pos2::ZERO);
+        assert_eq!(
+            shape.lines[0],
+            vec![
+                Pos2::new(0.0, -1.0),
+                Pos2::new(1.0, 0.0),
+                Pos2::new(0.0, 1.0),
+                Pos2::new(-1.0, 0.0),
+                Pos2::new(0.0, -1.0),
+            ]
+        );
+        assert!(shape.openings().is_empty());
+        assert!(shape
+            .all_segments()
+            .iter()
+            .all(|(start, end)| start.distance(*end) > 1e-4));
+    }
+}
@@ -0,0 +1,185 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use bouncy::{
+    drawable::Drawable,
+    game::Game,
+    level::{Level, LevelBundle},
+};
+
+pub type FileResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "toml")
+}
+
+/// Writes the level as a single-level bundle, the format the game loads
+pub fn save(path: &Path, level: &Level) -> FileResult<()> {
+    if is_toml(path) {
+        return Err("levels can only be saved as JSON".into());
+    }
+
+    std::fs::write(path, to_json(level))?;
+    Ok(())
+}
+
+/// The level as pretty-printed bundle JSON
+pub fn to_json(level: &Level) -> String {
+    LevelBundle::from_level(level.clone()).save_to_json()
+}
+
+/// Writes a picture of the level at its starting position as SVG
+pub fn export_svg(path: &Path, level: &Level) -> FileResult<()> {
+    let game = Game::new(level.clone(), 1024.);
+    std::fs::write(path, game.draw_to_svg())?;
+    Ok(())
+}
+
+/// Reads a JSON or TOML level, migrating it from older versions if needed
+pub fn load(path: &Path) -> FileResult<Level> {
+    if is_toml(path) {
+        let document: toml_edit::DocumentMut = std::fs::read_to_string(path)?.parse()?;
+        return from_value(toml_to_json(document.as_item()));
+    }
+
+    let file = File::open(path)?;
+    from_value(serde_json::from_reader(BufReader::new(file))?)
+}
+
+/// Reads the first level of a bundle, or a bare level as saved before bundles existed
+pub fn from_json(json: &str) -> FileResult<Level> {
+    from_value(serde_json::from_str(json)?)
+}
+
+fn from_value(json: serde_json::Value) -> FileResult<Level> {
+    if json.get("levels").is_none() {
+        return Ok(serde_json::from_value(json)?);
+    }
+
+    let bundle: LevelBundle = serde_json::from_value(json)?;
+    let level = bundle.levels.into_iter().next();
+    Ok(level.ok_or("the bundle has no levels")?)
+}
+
+/// Converts parsed TOML to the equivalent JSON, so that TOML levels go through the same
+/// deserialization and migration as JSON ones
+fn toml_to_json(item: &toml_edit::Item) -> serde_json::Value {
+    match item {
+        toml_edit::Item::None => serde_json::Value::Null,
+        toml_edit::Item::Value(value) => toml_value_to_json(value),
+        toml_edit::Item::Table(table) => toml_table_to_json(table),
+        toml_edit::Item::ArrayOfTables(tables) => tables.iter().map(toml_table_to_json).collect(),
+    }
+}
+
+fn toml_table_to_json(table: &toml_edit::Table) -> serde_json::Value {
+    table
+        .iter()
+        .map(|(key, item)| (key.to_owned(), toml_to_json(item)))
+        .collect()
+}
+
+fn toml_value_to_json(value: &toml_edit::Value) -> serde_json::Value {
+    match value {
+        toml_edit::Value::String(s) => s.value().clone().into(),
+        toml_edit::Value::Integer(i) => (*i.value()).into(),
+        toml_edit::Value::Float(f) => (*f.value()).into(),
+        toml_edit::Value::Boolean(b) => (*b.value()).into(),
+        toml_edit::Value::Datetime(datetime) => datetime.value().to_string().into(),
+        toml_edit::Value::Array(array) => array.iter().map(toml_value_to_json).collect(),
+        toml_edit::Value::InlineTable(table) => table
+            .iter()
+            .map(|(key, value)| (key.to_owned(), toml_value_to_json(value)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("bouncy-{}.json", uuid::Uuid::new_v4()));
+        let mut level = Level::simple_polygon(5);
+        level.max_brake_work = 12.5;
+
+        save(&path, &level).unwrap();
+        let loaded = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.id, level.id);
+        assert_eq!(loaded.max_brake_work, 12.5);
+        assert_eq!(loaded.body.shape.lines, level.body.shape.lines);
+
+        assert!(save(Path::new("level.toml"), &level).is_err());
+    }
+
+    #[test]
+    fn test_from_json() {
+        let level = Level::simple_polygon(5);
+
+        let bundle: LevelBundle = serde_json::from_str(&to_json(&level)).unwrap();
+        assert_eq!(bundle.levels, vec![level.clone()]);
+        assert_eq!(from_json(&to_json(&level)).unwrap(), level);
+
+        let bare = serde_json::to_string(&level).unwrap();
+        assert_eq!(from_json(&bare).unwrap(), level);
+
+        let empty = LevelBundle {
+            levels: Vec::new(),
+            ..bundle
+        };
+        assert!(from_json(&empty.save_to_json()).is_err());
+    }
+
+    #[test]
+    fn test_load_toml() {
+        let path = std::env::temp_dir().join(format!("bouncy-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"
+            version = 1
+            id = "d7a97ce7-bd14-4a0f-88c4-5646fa0ed646"
+            name = "Old Square"
+            gravity = 9.81
+            max_time = { secs = 45, nanos = 0 }
+            max_work = 50.0
+
+            [body]
+            shape = { lines = [[
+                { x = 1.0, y = 0.0 },
+                { x = 0.0, y = 1.0 },
+                { x = -1.0, y = 0.0 },
+                { x = 0.0, y = -1.0 },
+            ]] }
+            center_of_rotation = { x = 0.0, y = 0.0 }
+            angle = 0.0
+            angular_velocity = 1.0
+            moment_of_inertia = 1.0
+            friction_coefficient = 0.7
+
+            [ball]
+            center = { x = 0.0, y = 0.0 }
+            radius = 0.05
+            velocity = { x = 0.0, y = 0.0 }
+
+            [input]
+            brake = { torque = 3.0, active = false }
+            motor = { torque = 1.0, active = true }
+            boost = { torque = 2.0, active = false }
+            "#,
+        )
+        .unwrap();
+
+        let loaded = load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let level = loaded.unwrap();
+        assert_eq!(level.name, "Old Square");
+        assert_eq!(level.version, bouncy::level::CURRENT_VERSION);
+        assert_eq!(level.body.shape.lines[0].len(), 4);
+        assert_eq!(level.balls.len(), 1);
+        assert_eq!(level.max_boost_work, 50.0);
+    }
+}
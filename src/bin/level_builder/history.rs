@@ -0,0 +1,88 @@
+use bouncy::shape::Shape;
+
+const MAX_HISTORY: usize = 50;
+
+/// Undo and redo stacks of shape snapshots
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Shape>,
+    redo_stack: Vec<Shape>,
+}
+
+fn push_capped(stack: &mut Vec<Shape>, shape: Shape) {
+    stack.push(shape);
+    if stack.len() > MAX_HISTORY {
+        stack.remove(0);
+    }
+}
+
+impl History {
+    /// Records the shape as it was before an edit
+    pub fn record(&mut self, shape: Shape) {
+        push_capped(&mut self.undo_stack, shape);
+        self.redo_stack.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn undo(&mut self, shape: &mut Shape) {
+        if let Some(previous) = self.undo_stack.pop() {
+            push_capped(&mut self.redo_stack, std::mem::replace(shape, previous));
+        }
+    }
+
+    pub fn redo(&mut self, shape: &mut Shape) {
+        if let Some(next) = self.redo_stack.pop() {
+            push_capped(&mut self.undo_stack, std::mem::replace(shape, next));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::Pos2;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::default();
+        let original = Shape::regular_polygon(4, 1.0, Pos2::ZERO);
+        let mut shape = original.clone();
+
+        history.record(shape.clone());
+        shape.lines[0][1] = Pos2::new(0.0, 2.0);
+        let after_first = shape.clone();
+
+        history.record(shape.clone());
+        shape.lines[0][2] = Pos2::new(-2.0, 0.0);
+        let after_second = shape.clone();
+
+        history.undo(&mut shape);
+        assert_eq!(shape.lines, after_first.lines);
+        assert!(history.can_redo());
+
+        history.redo(&mut shape);
+        assert_eq!(shape.lines, after_second.lines);
+        assert!(!history.can_redo());
+
+        history.undo(&mut shape);
+        history.undo(&mut shape);
+        assert_eq!(shape.lines, original.lines);
+        assert!(!history.can_undo());
+
+        // A new edit discards anything that could be redone
+        history.record(shape.clone());
+        assert!(!history.can_redo());
+
+        for _ in 0..2 * MAX_HISTORY {
+            history.record(shape.clone());
+        }
+        assert_eq!(history.undo_stack.len(), MAX_HISTORY);
+    }
+}
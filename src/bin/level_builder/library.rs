@@ -0,0 +1,65 @@
+use bouncy::{level::LevelBundle, shape::Shape};
+use egui::Pos2;
+
+/// A ready-made shape that can be dropped into the level
+pub struct Preset {
+    pub name: String,
+    pub shape: Shape,
+}
+
+impl Preset {
+    fn new(name: impl Into<String>, shape: Shape) -> Self {
+        Self {
+            name: name.into(),
+            shape,
+        }
+    }
+}
+
+/// Preset shapes of unit radius, centred on the origin
+pub fn presets() -> Vec<Preset> {
+    let mut presets: Vec<Preset> = (3..=12)
+        .map(|num_sides| {
+            Preset::new(
+                format!("{num_sides}-gon"),
+                Shape::regular_polygon(num_sides, 1.0, Pos2::ZERO),
+            )
+        })
+        .collect();
+
+    presets.extend([5, 8].map(|num_points| {
+        Preset::new(
+            format!("{num_points}-point star"),
+            Shape::star_polygon(num_points, 1.0, 0.5, Pos2::ZERO),
+        )
+    }));
+
+    presets.extend([2., 3.].map(|ratio| {
+        Preset::new(
+            format!("{ratio}:1 ellipse"),
+            Shape::ellipse(32, egui::vec2(1.0, 1.0 / ratio), Pos2::ZERO),
+        )
+    }));
+
+    presets.extend(
+        LevelBundle::builtin()
+            .levels
+            .into_iter()
+            .filter(|level| level.id_name == "funky-polygon")
+            .map(|level| Preset::new(level.name, level.body.shape)),
+    );
+
+    presets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let presets = presets();
+        assert_eq!(presets.len(), 10 + 2 + 2 + 1);
+        assert!(presets.iter().any(|preset| preset.name == "Funky Polygon"));
+    }
+}
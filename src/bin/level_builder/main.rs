@@ -0,0 +1,35 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+#[cfg(not(target_arch = "wasm32"))]
+mod builder;
+#[cfg(not(target_arch = "wasm32"))]
+mod canvas;
+#[cfg(not(target_arch = "wasm32"))]
+mod file;
+#[cfg(not(target_arch = "wasm32"))]
+mod history;
+#[cfg(not(target_arch = "wasm32"))]
+mod library;
+#[cfg(not(target_arch = "wasm32"))]
+mod playtest;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> eframe::Result {
+    env_logger::init();
+
+    let native_options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([1024.0, 640.0])
+            .with_min_inner_size([640.0, 400.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Level Builder",
+        native_options,
+        Box::new(|cc| Ok(Box::new(builder::LevelBuilder::new(cc)))),
+    )
+}
+
+// The builder is a desktop tool only
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,155 @@
+use bouncy::{
+    drawable::Drawable,
+    game::{self, Game, RunStats},
+    level::Level,
+};
+use egui::{Color32, RichText};
+
+const TICK_RATE: f32 = 1024.;
+
+/// How a test play ended
+pub struct TestResult {
+    pub outcome: &'static str,
+    pub elapsed: f32,
+    pub stats: RunStats,
+}
+
+/// A game of the level being edited, played inside the builder
+pub struct TestPlay {
+    game: Game,
+}
+
+impl TestPlay {
+    pub fn new(level: Level) -> Self {
+        Self {
+            game: Game::new(level, TICK_RATE),
+        }
+    }
+
+    /// Shows the game, returning the result once it is over or has been stopped
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<TestResult> {
+        let game = &mut self.game;
+        let outcome = match game.update() {
+            game::State::Victory => Some("Escaped"),
+            game::State::Defeat => Some("Out of time"),
+            game::State::Playing => None,
+        };
+        ctx.request_repaint();
+
+        let mut stopped = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+
+        egui::TopBottomPanel::top("test_play").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("⏹ Stop").clicked() {
+                    stopped = true;
+                }
+
+                let remaining =
+                    game.level.max_time.as_secs_f32() - game.scaled_elapsed.as_secs_f32();
+                ui.label(format!("{:.1}s", remaining.max(0.0)));
+
+                let brake = game.input_work.brake_fraction(game.brake_budget());
+                let boost = game.input_work.boost_fraction(game.boost_budget());
+                ui.add(
+                    egui::ProgressBar::new(brake)
+                        .desired_width(120.)
+                        .fill(Color32::LIGHT_RED)
+                        .text(format!("Brake: {:.0} %", brake * 100.)),
+                );
+                ui.add(
+                    egui::ProgressBar::new(boost)
+                        .desired_width(120.)
+                        .fill(Color32::LIGHT_GREEN)
+                        .text(format!("Boost: {:.0} %", boost * 100.)),
+                );
+            });
+        });
+
+        egui::TopBottomPanel::bottom("test_controls").show(ctx, |ui| {
+            ui.columns(2, |ui| {
+                let control = |ui: &mut egui::Ui, enabled: bool, label: &str, fill: Color32| {
+                    ui.add_enabled_ui(enabled, |ui| {
+                        ui.add_sized(
+                            egui::vec2(ui.available_width(), 50.0),
+                            egui::Button::new(
+                                RichText::new(label)
+                                    .strong()
+                                    .heading()
+                                    .color(Color32::BLACK),
+                            )
+                            .fill(fill),
+                        )
+                    })
+                    .inner
+                    .is_pointer_button_down_on()
+                };
+
+                let brake = control(
+                    &mut ui[0],
+                    game.brake_enabled(),
+                    "Brake",
+                    Color32::LIGHT_RED,
+                );
+                let boost = control(
+                    &mut ui[1],
+                    game.boost_enabled(),
+                    "Boost",
+                    Color32::LIGHT_GREEN,
+                );
+                let (brake_key, boost_key) = ui[0].input(|i| {
+                    (
+                        i.key_down(egui::Key::ArrowLeft),
+                        i.key_down(egui::Key::ArrowRight),
+                    )
+                });
+
+                game.level.input.brake.active = brake || brake_key;
+                game.level.input.boost.active = boost || boost_key;
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let (response, painter) =
+                ui.allocate_painter(ui.available_size(), egui::Sense::hover());
+            game.draw_background(&painter, response.rect);
+            game.draw(ui.ctx(), &painter, game.view_transform(response.rect));
+        });
+
+        let outcome = outcome.or(stopped.then_some("Stopped"));
+        outcome.map(|outcome| TestResult {
+            outcome,
+            elapsed: game.scaled_elapsed.as_secs_f32(),
+            stats: game.stats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_stops_test_play() {
+        let mut test_play = TestPlay::new(Level::simple_polygon(6));
+
+        let ctx = egui::Context::default();
+        let mut result = None;
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            assert!(test_play.show(ctx).is_none());
+        });
+
+        let input = egui::RawInput {
+            events: vec![egui::Event::Key {
+                key: egui::Key::Escape,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: egui::Modifiers::NONE,
+            }],
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| result = test_play.show(ctx));
+
+        assert_eq!(result.map(|result| result.outcome), Some("Stopped"));
+    }
+}
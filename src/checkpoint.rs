@@ -0,0 +1,66 @@
+use egui::{emath::TSTransform, Color32, Pos2};
+use serde::{Deserialize, Serialize};
+
+/// A marker partway through a level that records a split time when a ball first reaches it.
+/// Defined in the body's frame so it rotates with the body, like an escape zone.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Checkpoint {
+    pub position: Pos2,
+    pub radius: f32,
+    pub label: String,
+}
+
+impl Checkpoint {
+    pub fn new(position: Pos2, radius: f32, label: impl Into<String>) -> Self {
+        Self {
+            position,
+            radius,
+            label: label.into(),
+        }
+    }
+
+    pub fn rotate(&self, angle: f32, center_of_rotation: Pos2) -> Self {
+        let offset = self.position - center_of_rotation;
+        let (sin, cos) = angle.sin_cos();
+        let offset = egui::vec2(
+            offset.x * cos - offset.y * sin,
+            offset.x * sin + offset.y * cos,
+        );
+
+        Self {
+            position: center_of_rotation + offset,
+            ..self.clone()
+        }
+    }
+
+    pub fn contains(&self, point: Pos2) -> bool {
+        self.position.distance(point) <= self.radius
+    }
+
+    pub fn draw(&self, painter: &egui::Painter, transform: TSTransform, reached: bool) {
+        let center = transform.mul_pos(self.position);
+        let radius = self.radius * transform.scaling;
+
+        let colour = Color32::from_rgb(80, 160, 255);
+        if reached {
+            painter.circle_filled(center, radius, colour.gamma_multiply(0.3));
+        }
+        painter.circle_stroke(center, radius, egui::Stroke::new(1.5, colour));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_rotates_with_body() {
+        let checkpoint = Checkpoint::new(Pos2::new(0.5, 0.0), 0.1, "CP1");
+        assert!(checkpoint.contains(Pos2::new(0.55, 0.05)));
+
+        let rotated = checkpoint.rotate(std::f32::consts::FRAC_PI_2, Pos2::ZERO);
+        assert!(!rotated.contains(Pos2::new(0.5, 0.0)));
+        assert!(rotated.contains(Pos2::new(0.0, 0.5)));
+        assert_eq!(rotated.label, "CP1");
+    }
+}
@@ -6,11 +6,33 @@ use crate::{ball::Ball, shape::Segment};
 pub struct Collision {
     pub point: Pos2,
     pub normal: Vec2,
+    pub surface_velocity: Vec2,
+    /// Speed of the ball along the normal, relative to the surface, when it hit
+    pub velocity_at_impact: f32,
 }
 
 impl Collision {
     pub fn new(point: Pos2, normal: Vec2) -> Self {
-        Self { point, normal }
+        Self {
+            point,
+            normal,
+            surface_velocity: Vec2::ZERO,
+            velocity_at_impact: 0.0,
+        }
+    }
+
+    pub fn with_surface_velocity(self, surface_velocity: Vec2) -> Self {
+        Self {
+            surface_velocity,
+            ..self
+        }
+    }
+
+    pub fn with_velocity_at_impact(self, velocity_at_impact: f32) -> Self {
+        Self {
+            velocity_at_impact,
+            ..self
+        }
     }
 
     pub fn rotate(&self, angle: f32, center_of_rotation: Pos2) -> Self {
@@ -32,11 +54,15 @@ impl Collision {
             )
         };
 
-        Self { point, normal }
+        Self {
+            point,
+            normal,
+            ..*self
+        }
     }
 }
 
-pub fn detect_collision(segment: Segment, ball: Ball) -> Option<Collision> {
+pub fn detect_collision(segment: Segment, ball: &Ball) -> Option<Collision> {
     let p1 = segment.0;
     let p2 = segment.1;
 
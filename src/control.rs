@@ -2,13 +2,13 @@ use std::ops::AddAssign;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub struct Input {
     pub torque: f32,
     pub active: bool,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub struct InputSet {
     pub brake: Input,
     pub motor: Input,
@@ -22,6 +22,30 @@ pub struct InputSetWork {
     pub boost: f32,
 }
 
+impl InputSetWork {
+    /// Work spent on the player's inputs; the motor runs for free
+    pub fn total(&self) -> f32 {
+        self.brake + self.boost
+    }
+
+    pub fn brake_fraction(&self, max_brake: f32) -> f32 {
+        remaining_fraction(self.brake, max_brake)
+    }
+
+    pub fn boost_fraction(&self, max_boost: f32) -> f32 {
+        remaining_fraction(self.boost, max_boost)
+    }
+}
+
+/// Fraction of `max` that is left after spending `spent`, clamped to `[0, 1]`
+fn remaining_fraction(spent: f32, max: f32) -> f32 {
+    if max <= 0.0 {
+        return 0.0;
+    }
+
+    ((max - spent) / max).clamp(0.0, 1.0)
+}
+
 impl AddAssign<InputSetWork> for InputSetWork {
     fn add_assign(&mut self, rhs: InputSetWork) {
         self.brake += rhs.brake;
@@ -29,3 +53,33 @@ impl AddAssign<InputSetWork> for InputSetWork {
         self.boost += rhs.boost;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_set_work_total() {
+        let work = InputSetWork {
+            brake: 10.0,
+            boost: 5.0,
+            motor: 0.0,
+        };
+
+        assert_eq!(work.total(), 15.0);
+        assert_eq!(work.brake_fraction(20.0), 0.5);
+        assert_eq!(work.boost_fraction(20.0), 0.75);
+    }
+
+    #[test]
+    fn test_input_set_work_fraction_clamps() {
+        let work = InputSetWork {
+            brake: 30.0,
+            boost: 0.0,
+            motor: 0.0,
+        };
+
+        assert_eq!(work.brake_fraction(20.0), 0.0);
+        assert_eq!(work.boost_fraction(20.0), 1.0);
+    }
+}
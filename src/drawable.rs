@@ -2,4 +2,9 @@ use egui::emath::TSTransform;
 
 pub trait Drawable {
     fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform);
+
+    /// SVG elements for the same picture, in world coordinates
+    fn draw_to_svg(&self) -> String {
+        String::new()
+    }
 }
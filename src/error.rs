@@ -0,0 +1,46 @@
+/// Logic errors that end a run but shouldn't bring down the whole app
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    /// A screen was shown while the app was in some other state
+    InvalidState {
+        found: String,
+    },
+    LevelNotFound(uuid::Uuid),
+    /// Part of the simulation stopped being a finite number
+    PhysicsNaN {
+        field: &'static str,
+    },
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::InvalidState { found } => write!(f, "invalid game state: {}", found),
+            GameError::LevelNotFound(id) => write!(f, "level {} not found", id),
+            GameError::PhysicsNaN { field } => write!(f, "{} is no longer a finite number", field),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = GameError::InvalidState {
+            found: "Menu".to_owned(),
+        };
+        assert_eq!(error.to_string(), "invalid game state: Menu");
+
+        let error = GameError::PhysicsNaN {
+            field: "ball.center",
+        };
+        assert_eq!(
+            error.to_string(),
+            "ball.center is no longer a finite number"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use egui::{emath::TSTransform, Color32, Pos2};
+use serde::{Deserialize, Serialize};
+
+use crate::shape::{distance_to_segment, segments_intersect, Segment};
+
+/// A marked exit gap, defined in the body's frame so it rotates with the body.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct EscapeZone {
+    pub start: Pos2,
+    pub end: Pos2,
+}
+
+impl EscapeZone {
+    pub fn new(start: Pos2, end: Pos2) -> Self {
+        Self { start, end }
+    }
+
+    pub fn segment(&self) -> Segment {
+        (self.start, self.end)
+    }
+
+    pub fn rotate(&self, angle: f32, center_of_rotation: Pos2) -> Self {
+        let rotate_point = |p: Pos2| {
+            let p = p - center_of_rotation;
+            let p = egui::vec2(
+                p.x * angle.cos() - p.y * angle.sin(),
+                p.x * angle.sin() + p.y * angle.cos(),
+            );
+            center_of_rotation + p
+        };
+
+        Self {
+            start: rotate_point(self.start),
+            end: rotate_point(self.end),
+        }
+    }
+
+    pub fn is_crossed_by(&self, previous: Pos2, current: Pos2) -> bool {
+        segments_intersect((previous, current), self.segment())
+    }
+
+    pub fn distance_to(&self, point: Pos2) -> f32 {
+        distance_to_segment(point, self.segment())
+    }
+
+    pub fn draw(&self, painter: &egui::Painter, transform: TSTransform, opacity: f32) {
+        let points = [transform.mul_pos(self.start), transform.mul_pos(self.end)];
+
+        let colour = Color32::from_rgba_unmultiplied(0, 200, 0, (255. * opacity) as u8);
+        let stroke = egui::Stroke::new(2.0, colour);
+        painter.extend(egui::Shape::dashed_line(&points, stroke, 6.0, 4.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_zone_crossing() {
+        let zone = EscapeZone::new(Pos2::new(1.0, -0.1), Pos2::new(1.0, 0.1));
+
+        assert!(zone.is_crossed_by(Pos2::new(0.95, 0.0), Pos2::new(1.05, 0.0)));
+        assert!(!zone.is_crossed_by(Pos2::new(0.9, 0.0), Pos2::new(0.95, 0.0)));
+        assert!(!zone.is_crossed_by(Pos2::new(0.95, 0.5), Pos2::new(1.05, 0.5)));
+    }
+
+    #[test]
+    fn test_escape_zone_rotates_with_body() {
+        let zone = EscapeZone::new(Pos2::new(1.0, -0.1), Pos2::new(1.0, 0.1));
+        let rotated = zone.rotate(std::f32::consts::FRAC_PI_2, Pos2::ZERO);
+
+        assert!(rotated.is_crossed_by(Pos2::new(0.0, 0.95), Pos2::new(0.0, 1.05)));
+    }
+}
@@ -0,0 +1,119 @@
+use egui::{emath::TSTransform, Color32, Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::drawable::Drawable;
+
+/// A point mass that pulls the ball towards its center with inverse-square falloff, but only
+/// within `radius`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct GravityWell {
+    pub center: Pos2,
+    pub strength: f32,
+    pub radius: f32,
+}
+
+impl GravityWell {
+    pub fn acceleration_at(&self, point: Pos2) -> Vec2 {
+        let offset = self.center - point;
+        let distance = offset.length();
+
+        if distance > self.radius || distance == 0.0 {
+            return Vec2::ZERO;
+        }
+
+        self.strength * offset.normalized() / (distance * distance)
+    }
+}
+
+impl Drawable for GravityWell {
+    fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
+        let center = transform.mul_pos(self.center);
+        let radius = self.radius * transform.scaling;
+
+        let colour = ctx.style().visuals.selection.bg_fill;
+        let fill = Color32::from_rgba_unmultiplied(colour.r(), colour.g(), colour.b(), 40);
+        painter.add(egui::Shape::circle_filled(center, radius, fill));
+    }
+}
+
+/// A region of constant acceleration, e.g. a wind corridor.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ForceField {
+    pub region: Rect,
+    pub force: Vec2,
+}
+
+impl ForceField {
+    pub fn acceleration_at(&self, point: Pos2) -> Vec2 {
+        if self.region.contains(point) {
+            self.force
+        } else {
+            Vec2::ZERO
+        }
+    }
+}
+
+impl Drawable for ForceField {
+    fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
+        let region = transform.mul_rect(self.region);
+
+        let colour = ctx.style().visuals.selection.bg_fill;
+        let fill = Color32::from_rgba_unmultiplied(colour.r(), colour.g(), colour.b(), 30);
+        painter.rect_filled(region, 0.0, fill);
+
+        if self.force == Vec2::ZERO {
+            return;
+        }
+
+        let arrow_length = 0.4 * region.size().min_elem();
+        let direction = arrow_length * self.force.normalized();
+        let stroke_colour =
+            Color32::from_rgba_unmultiplied(colour.r(), colour.g(), colour.b(), 120);
+        painter.arrow(
+            region.center() - direction / 2.,
+            direction,
+            egui::Stroke::new(2.0, stroke_colour),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gravity_well_inside_radius() {
+        let well = GravityWell {
+            center: Pos2::new(1.0, 0.0),
+            strength: 2.0,
+            radius: 1.0,
+        };
+
+        let acceleration = well.acceleration_at(Pos2::new(0.5, 0.0));
+        assert!((acceleration - Vec2::new(2.0 / 0.25, 0.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_gravity_well_outside_radius() {
+        let well = GravityWell {
+            center: Pos2::new(1.0, 0.0),
+            strength: 2.0,
+            radius: 1.0,
+        };
+
+        assert_eq!(well.acceleration_at(Pos2::new(-0.5, 0.0)), Vec2::ZERO);
+        assert_eq!(well.acceleration_at(well.center), Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_force_field_region() {
+        let field = ForceField {
+            region: Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0)),
+            force: Vec2::new(3.0, 0.0),
+        };
+
+        assert_eq!(field.acceleration_at(Pos2::new(0.5, 0.5)), field.force);
+        assert_eq!(field.acceleration_at(Pos2::new(1.0, 0.5)), field.force);
+        assert_eq!(field.acceleration_at(Pos2::new(1.5, 0.5)), Vec2::ZERO);
+    }
+}
@@ -1,13 +1,21 @@
-use egui::{emath::TSTransform, Pos2, Vec2};
+use std::collections::{BinaryHeap, HashSet};
+
+use egui::{emath::TSTransform, Pos2, Rect, Vec2};
 use ringbuffer::RingBuffer;
 
 use crate::{
+    ball::Ball,
+    checkpoint::Checkpoint,
     collision,
     control::{Input, InputSet, InputSetWork},
     drawable::Drawable,
-    level::Level,
+    error::GameError,
+    escape::EscapeZone,
+    field::ForceField,
+    level::{Level, LevelAction},
     rotating::{self, CollisionList},
-    shape::compute_winding_number,
+    shape::{compute_winding_number, distance_to_segment, segments_intersect, Segment},
+    tutorial::{TutorialState, TutorialTrigger},
 };
 
 #[derive(Debug)]
@@ -17,6 +25,127 @@ pub enum State {
     Defeat,
 }
 
+const SOLVER_DECISIONS_PER_SEC: f32 = 8.0;
+const SOLVER_STATE_RESOLUTION: f32 = 1e-3;
+
+const OPTIMAL_ESCAPE_LOOKAHEAD_SECS: f32 = 10.0;
+const OPTIMAL_ESCAPE_STATE_RESOLUTION: f32 = 1e-2;
+/// Enough for the escapes on the shipped levels, while keeping the estimate under a second in a
+/// release build rather than freezing the debug window on levels that are hard to escape
+const OPTIMAL_ESCAPE_MAX_BRANCHES: usize = 2048;
+
+const TRAIL_POINTS_PER_SEC: f32 = 60.0;
+
+/// Ten minutes of samples, one per second
+const WORK_HISTORY_LENGTH: usize = 600;
+
+const RESPAWN_PENALTY: web_time::Duration = web_time::Duration::from_secs(5);
+
+const REWIND_SECS: f32 = 2.0;
+const REWIND_ANIMATION_SECS: f32 = 0.5;
+
+/// Everything the player did during a run, enough to replay it exactly
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InputLog {
+    /// Whether brake, boost and motor were active, listed by tick
+    pub inputs: Vec<(u64, bool, bool, bool)>,
+    /// Ticks that rewinds went back to, where a replay charges their cost again
+    pub rewinds: Vec<u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunStats {
+    pub total_collisions: u32,
+    pub distance_traveled: f32,
+    pub max_speed: f32,
+    pub work_spent_brake: f32,
+    pub work_spent_boost: f32,
+    /// Ticks where braking overshot and reversed the spin of the body
+    pub brake_reversals: u32,
+}
+
+/// Simulation state at the end of a tick, kept so that play can be rolled back
+#[derive(Debug, Clone)]
+pub struct TickSnapshot {
+    pub tick: u64,
+    pub balls: Vec<Ball>,
+    pub body_angle: f32,
+    pub body_angular_velocity: f32,
+    pub input_work: InputSetWork,
+    /// Parts of the level that events and respawns change during play
+    pub gravity: Vec2,
+    pub max_brake_work: f32,
+    pub max_boost_work: f32,
+    pub angular_velocity_limit: Option<(f32, f32)>,
+    pub force_fields: Vec<ForceField>,
+    /// Where each moving platform is along its waypoints, as (current waypoint, progress)
+    pub platforms: Vec<(usize, f32)>,
+    pub events_fired: usize,
+    pub escaped: bool,
+    pub checkpoint_times: Vec<Option<f32>>,
+    pub tutorial_state: TutorialState,
+    pub stats: RunStats,
+    pub score: u32,
+    pub combo: u32,
+    pub last_collision_tick: Option<u64>,
+}
+
+/// Plays back the snapshots undone by a rewind, newest first
+#[derive(Debug, Clone)]
+pub struct RewindAnimation {
+    pub frames: Vec<TickSnapshot>,
+    pub progress: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollbackError {
+    HistoryDisabled,
+    InsufficientHistory,
+}
+
+impl std::fmt::Display for RollbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RollbackError::HistoryDisabled => write!(f, "tick history is not enabled"),
+            RollbackError::InsufficientHistory => write!(f, "not enough tick history"),
+        }
+    }
+}
+
+impl std::error::Error for RollbackError {}
+
+/// Distances from the center of rotation to the nearest and furthest points of the body's
+/// walls. Rotation doesn't change them, so they only need finding once per game.
+#[derive(Debug, Clone, Copy)]
+pub struct BodyBounds {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+}
+
+impl BodyBounds {
+    pub fn new(body: &crate::rotating::Body) -> Self {
+        let center = body.center_of_rotation;
+        Self {
+            inner_radius: body.shape.inner_radius(center),
+            outer_radius: body.shape.max_extent(center).width() / 2.,
+        }
+    }
+
+    /// Whether the ball could be touching a wall, whatever angle the body is at
+    pub fn may_collide(&self, center_of_rotation: Pos2, ball: &Ball) -> bool {
+        // Clear of the walls, well inside the body
+        let distance = (ball.center - center_of_rotation).length();
+        if distance < self.inner_radius - ball.radius - 1e-3 {
+            return false;
+        }
+
+        let ball_bounds = Rect::from_center_size(ball.center, Vec2::splat(2.0 * ball.radius));
+        let body_bounds =
+            Rect::from_center_size(center_of_rotation, Vec2::splat(2.0 * self.outer_radius));
+        ball_bounds.intersects(body_bounds)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Game {
     pub start_time: web_time::Instant,
@@ -27,12 +156,70 @@ pub struct Game {
     pub level: Level,
     pub input_work: InputSetWork,
     pub collision_list: CollisionList,
+    pub escaped: bool,
+    /// Time at which each of the level's checkpoints was first reached
+    pub checkpoint_times: Vec<Option<f32>>,
+    pub events_fired: usize,
+    pub tutorial_state: TutorialState,
+    pub paused: bool,
+    pub pause_start: web_time::Instant,
+    pub total_paused_duration: web_time::Duration,
+    pub time_scale: f32,
+    /// Play time with `time_scale` applied, which drives the physics
+    pub scaled_elapsed: web_time::Duration,
+    last_elapsed: web_time::Duration,
+    pub stats: RunStats,
+    pub record_inputs: bool,
+    pub input_log: InputLog,
+    pub replay: Option<InputLog>,
+    pub score: u32,
+    pub combo: u32,
+    /// Tick of the previous collision, used instead of wall-clock time to keep replays exact
+    pub last_collision_tick: Option<u64>,
+    pub speed_run_mode: bool,
+    pub ball_initial_state: Vec<Ball>,
+    /// Work spent on rewinds, split evenly between the brake and boost budgets. Kept out of the
+    /// tick snapshots so that rolling back never refunds it.
+    pub rewind_work: f32,
+    pub tick_history: Option<ringbuffer::AllocRingBuffer<TickSnapshot>>,
+    pub rewind_animation: Option<RewindAnimation>,
+    /// Bounds of the body's shape, which stays fixed during the game apart from rotating
+    pub body_bounds: BodyBounds,
+    /// Updates on which the physics was more than a second behind the clock
+    pub tick_overruns: u32,
+    /// Respawn penalties added to the clock since the last update, which the physics has to
+    /// catch up with but which don't count as falling behind
+    respawn_penalty: web_time::Duration,
+    /// Total brake and boost work spent, sampled once per second as (time, work)
+    pub work_history: ringbuffer::AllocRingBuffer<(f32, f32)>,
 }
 
 impl Game {
-    pub fn new(level: Level, tick_rate: f32) -> Self {
+    pub fn new(mut level: Level, tick_rate: f32) -> Self {
+        // Events are fired in order using a cursor, so they must be sorted by trigger time
+        level
+            .events
+            .sort_by(|a, b| a.trigger_time_secs.total_cmp(&b.trigger_time_secs));
+        level.body.angle = level.initial_body_angle;
+        for ball in &mut level.balls {
+            ball.velocity += level.ball_initial_velocity;
+            ball.color = ball.color.or(level.ball_color);
+        }
+
+        let mut tutorial_state = TutorialState::default();
+        tutorial_state.notify(TutorialTrigger::OnGameStart);
+        tutorial_state.advance(&level.tutorial_steps, 0);
+
+        let now = web_time::Instant::now();
+        let speed_run_mode = level.is_practice;
+        let body_bounds = BodyBounds::new(&level.body);
+        let checkpoint_times = vec![None; level.checkpoints.len()];
+        let mut work_history = ringbuffer::AllocRingBuffer::new(WORK_HISTORY_LENGTH);
+        work_history.push((0.0, 0.0));
+        let ball_initial_state = level.balls.clone();
+
         Self {
-            start_time: web_time::Instant::now(),
+            start_time: now,
             tick_rate,
             tick_dt: 1.0 / tick_rate,
             tick_counter: 0,
@@ -40,146 +227,1985 @@ impl Game {
             level,
             input_work: InputSetWork::default(),
             collision_list: CollisionList::new(1024),
+            escaped: false,
+            checkpoint_times,
+            events_fired: 0,
+            tutorial_state,
+            paused: false,
+            pause_start: now,
+            total_paused_duration: web_time::Duration::ZERO,
+            time_scale: 1.0,
+            scaled_elapsed: web_time::Duration::ZERO,
+            last_elapsed: web_time::Duration::ZERO,
+            stats: RunStats::default(),
+            record_inputs: false,
+            input_log: InputLog::default(),
+            replay: None,
+            score: 0,
+            combo: 0,
+            last_collision_tick: None,
+            speed_run_mode,
+            ball_initial_state,
+            rewind_work: 0.0,
+            tick_history: None,
+            rewind_animation: None,
+            body_bounds,
+            tick_overruns: 0,
+            respawn_penalty: web_time::Duration::ZERO,
+            work_history,
+        }
+    }
+
+    /// Feeds inputs from a recorded log instead of the live input state
+    pub fn with_replay(mut self, log: InputLog) -> Self {
+        self.replay = Some(log);
+        self
+    }
+
+    /// Keeps a snapshot of the last `capacity` ticks so that they can be rolled back
+    pub fn with_history(mut self, capacity: usize) -> Self {
+        let mut history = ringbuffer::AllocRingBuffer::new(capacity);
+        history.push(self.snapshot());
+        self.tick_history = Some(history);
+        self
+    }
+
+    fn snapshot(&self) -> TickSnapshot {
+        TickSnapshot {
+            tick: self.tick_counter,
+            balls: self.level.balls.clone(),
+            body_angle: self.level.body.angle,
+            body_angular_velocity: self.level.body.angular_velocity,
+            input_work: self.input_work,
+            gravity: self.level.gravity,
+            max_brake_work: self.level.max_brake_work,
+            max_boost_work: self.level.max_boost_work,
+            angular_velocity_limit: self.level.body.angular_velocity_limit,
+            force_fields: self.level.force_fields.clone(),
+            platforms: self
+                .level
+                .moving_platform
+                .iter()
+                .map(|platform| (platform.current_waypoint, platform.progress))
+                .collect(),
+            events_fired: self.events_fired,
+            escaped: self.escaped,
+            checkpoint_times: self.checkpoint_times.clone(),
+            tutorial_state: self.tutorial_state.clone(),
+            stats: self.stats,
+            score: self.score,
+            combo: self.combo,
+            last_collision_tick: self.last_collision_tick,
+        }
+    }
+
+    fn restore(&mut self, snapshot: TickSnapshot) {
+        self.tick_counter = snapshot.tick;
+        self.level.balls = snapshot.balls;
+        self.level.body.angle = snapshot.body_angle;
+        self.level.body.angular_velocity = snapshot.body_angular_velocity;
+        self.input_work = snapshot.input_work;
+        self.level.gravity = snapshot.gravity;
+        self.level.max_brake_work = snapshot.max_brake_work;
+        self.level.max_boost_work = snapshot.max_boost_work;
+        self.level.body.angular_velocity_limit = snapshot.angular_velocity_limit;
+        self.level.force_fields = snapshot.force_fields;
+        for (platform, (current_waypoint, progress)) in self
+            .level
+            .moving_platform
+            .iter_mut()
+            .zip(snapshot.platforms)
+        {
+            platform.current_waypoint = current_waypoint;
+            platform.progress = progress;
+        }
+        self.events_fired = snapshot.events_fired;
+        self.escaped = snapshot.escaped;
+        self.checkpoint_times = snapshot.checkpoint_times;
+        self.tutorial_state = snapshot.tutorial_state;
+        self.stats = snapshot.stats;
+        self.score = snapshot.score;
+        self.combo = snapshot.combo;
+        self.last_collision_tick = snapshot.last_collision_tick;
+    }
+
+    /// Restores the physics state from `n_ticks` ago
+    pub fn rollback(&mut self, n_ticks: u32) -> Result<(), RollbackError> {
+        let history = self
+            .tick_history
+            .as_mut()
+            .ok_or(RollbackError::HistoryDisabled)?;
+
+        // The newest snapshot is the current state, so one more than `n_ticks` is needed
+        let n_ticks = n_ticks as usize;
+        if history.len() <= n_ticks {
+            return Err(RollbackError::InsufficientHistory);
+        }
+
+        let mut snapshots = history.to_vec();
+        snapshots.truncate(snapshots.len() - n_ticks);
+        history.clear();
+        snapshots
+            .iter()
+            .cloned()
+            .for_each(|snapshot| history.push(snapshot));
+
+        let snapshot = snapshots.pop().unwrap();
+        let rolled_back_ticks = self.tick_counter - snapshot.tick;
+
+        self.restore(snapshot);
+
+        // Wind the clock back too, otherwise the next update simulates the same ticks again
+        self.scaled_elapsed =
+            self.scaled_elapsed
+                .saturating_sub(web_time::Duration::from_secs_f32(
+                    rolled_back_ticks as f32 * self.tick_dt,
+                ));
+        self.input_log
+            .inputs
+            .retain(|(tick, _, _, _)| *tick <= self.tick_counter);
+        let time = self.tick_counter as f32 * self.tick_dt;
+        let work_history: Vec<_> = self
+            .work_history
+            .iter()
+            .copied()
+            .filter(|(sample_time, _)| *sample_time <= time)
+            .collect();
+        self.work_history.clear();
+        work_history
+            .into_iter()
+            .for_each(|sample| self.work_history.push(sample));
+
+        Ok(())
+    }
+
+    pub fn rewind_ticks(&self) -> u32 {
+        (REWIND_SECS * self.tick_rate).round() as u32
+    }
+
+    pub fn can_rewind(&self) -> bool {
+        let enough_history = self
+            .tick_history
+            .as_ref()
+            .is_some_and(|history| history.len() > self.rewind_ticks() as usize);
+
+        enough_history
+            && self.rewind_animation.is_none()
+            && self.brake_work_remaining() >= self.level.rewind_cost / 2.
+            && self.boost_work_remaining() >= self.level.rewind_cost / 2.
+    }
+
+    /// Rolls the physics back a couple of seconds in exchange for some of the work budgets, split
+    /// evenly between brake and boost
+    pub fn rewind(&mut self) -> Result<(), RollbackError> {
+        let n_ticks = self.rewind_ticks();
+        let frames: Vec<TickSnapshot> = self
+            .tick_history
+            .as_ref()
+            .map(|history| {
+                history
+                    .iter()
+                    .rev()
+                    .take(n_ticks as usize + 1)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        self.rollback(n_ticks)?;
+        self.rewind_work += self.level.rewind_cost;
+
+        // Earlier rewinds to ticks that have just been undone are now paid for from this tick on
+        let tick = self.tick_counter;
+        for rewind in &mut self.input_log.rewinds {
+            *rewind = (*rewind).min(tick);
+        }
+        self.input_log.rewinds.push(tick);
+        self.rewind_animation = Some(RewindAnimation {
+            frames,
+            progress: 0.0,
+        });
+
+        Ok(())
+    }
+
+    /// Shows the next frame of the rewind animation, returning false once it has finished
+    fn animate_rewind(&mut self, frame_time: f32) -> bool {
+        let Some(animation) = self.rewind_animation.as_mut() else {
+            return false;
+        };
+
+        animation.progress += frame_time / REWIND_ANIMATION_SECS;
+        if animation.progress >= 1.0 || animation.frames.len() < 2 {
+            // The rollback already restored the final state, so just drop the animation
+            let target = animation.frames.last().cloned();
+            self.rewind_animation = None;
+            if let Some(target) = target {
+                self.level.balls = target.balls;
+                self.level.body.angle = target.body_angle;
+            }
+            return false;
+        }
+
+        let position = animation.progress * (animation.frames.len() - 1) as f32;
+        let (from, to) = (
+            &animation.frames[position.floor() as usize],
+            &animation.frames[position.ceil() as usize],
+        );
+        let t = position.fract();
+
+        self.level.body.angle = egui::lerp(from.body_angle..=to.body_angle, t);
+        for (ball, (from, to)) in self
+            .level
+            .balls
+            .iter_mut()
+            .zip(from.balls.iter().zip(to.balls.iter()))
+        {
+            ball.center = from.center.lerp(to.center, t);
+        }
+
+        true
+    }
+
+    /// Whether any sequence of brake and boost inputs escapes within `max_ticks`, returning the
+    /// number of ticks taken by the first escape found. This is a feasibility check: the search
+    /// follows the furthest branch first, so the escape it finds is usually not the fastest one.
+    pub fn find_escape(&self, max_ticks: u64) -> Option<u64> {
+        // Exploring the furthest branch first finds a run that escapes without any input without
+        // expanding every combination at each step. An earliest-first search is exponential in
+        // the time to escape and doesn't finish on the shipped levels.
+        self.search_for_escape(max_ticks, SOLVER_STATE_RESOLUTION, usize::MAX, |game| {
+            game.tick_counter
+        })
+    }
+
+    /// Estimates the earliest time in seconds since the start of the level at which the ball
+    /// could escape, looking up to ten seconds ahead. The search favours the branches closest
+    /// to escaping, so it is quick but not guaranteed to find the very fastest route, and it
+    /// gives up with `None` after a fixed number of branches.
+    pub fn optimal_escape_time(&self) -> Option<f32> {
+        let max_ticks = (OPTIMAL_ESCAPE_LOOKAHEAD_SECS * self.tick_rate) as u64;
+        let ticks = self.search_for_escape(
+            max_ticks,
+            OPTIMAL_ESCAPE_STATE_RESOLUTION,
+            OPTIMAL_ESCAPE_MAX_BRANCHES,
+            |game| (game.escape_progress() * 1e6) as u64,
+        )?;
+
+        Some((self.tick_counter + ticks) as f32 / self.tick_rate)
+    }
+
+    /// Expands branches of the four brake and boost combinations, highest `priority` first,
+    /// until one escapes or `max_branches` have been created. Branches whose state rounds to one
+    /// already seen at `resolution` are dropped.
+    fn search_for_escape(
+        &self,
+        max_ticks: u64,
+        resolution: f32,
+        max_branches: usize,
+        priority: impl Fn(&Game) -> u64,
+    ) -> Option<u64> {
+        let shape = self.level.body.shape_with_rotation_applied();
+        if shape.openings().is_empty() && self.level.escape_zones.is_empty() {
+            return None;
+        }
+
+        let mut start = self.clone();
+        start.replay = None;
+        start.record_inputs = false;
+        start.tick_history = None;
+        start.rewind_animation = None;
+        let start_tick = start.tick_counter;
+        let decision_ticks = (self.tick_rate / SOLVER_DECISIONS_PER_SEC).max(1.0) as u64;
+
+        // The sequence number breaks ties so that earlier input combinations are tried first
+        let mut frontier = BinaryHeap::new();
+        frontier.push((priority(&start), u64::MAX, 0));
+        let mut branches = vec![Some(start)];
+        let mut visited = HashSet::new();
+        let mut sequence = u64::MAX;
+
+        while let Some((_, _, index)) = frontier.pop() {
+            if branches.len() >= max_branches {
+                return None;
+            }
+            let Some(game) = branches[index].take() else {
+                continue;
+            };
+
+            for (brake, boost) in [(false, false), (true, false), (false, true), (true, true)] {
+                let mut branch = game.clone();
+                branch.level.input.brake.active = brake;
+                branch.level.input.boost.active = boost;
+
+                for _ in 0..decision_ticks {
+                    branch.tick();
+                    if branch.has_escaped() {
+                        return Some(branch.tick_counter - start_tick);
+                    }
+                    if branch.tick_counter - start_tick >= max_ticks {
+                        break;
+                    }
+                }
+
+                if branch.tick_counter - start_tick >= max_ticks
+                    || !visited.insert(branch.solver_state_key(resolution))
+                {
+                    continue;
+                }
+
+                sequence -= 1;
+                frontier.push((priority(&branch), sequence, branches.len()));
+                branches.push(Some(branch));
+            }
+        }
+
+        None
+    }
+
+    /// Quantised ball and body state, used to prune solver branches that have converged
+    fn solver_state_key(&self, resolution: f32) -> Vec<i64> {
+        let quantise = |value: f32| (value / resolution).round() as i64;
+
+        let mut key = vec![
+            quantise(self.level.body.angle.rem_euclid(std::f32::consts::TAU)),
+            quantise(self.level.body.angular_velocity),
+            quantise(self.work_remaining()),
+        ];
+        for ball in &self.level.balls {
+            key.extend([
+                quantise(ball.center.x),
+                quantise(ball.center.y),
+                quantise(ball.velocity.x),
+                quantise(ball.velocity.y),
+            ]);
+        }
+        key
+    }
+
+    pub fn pause(&mut self) {
+        if !self.paused {
+            self.paused = true;
+            self.pause_start = web_time::Instant::now();
         }
     }
 
+    pub fn resume(&mut self) {
+        if self.paused {
+            self.paused = false;
+            self.total_paused_duration += web_time::Instant::now() - self.pause_start;
+        }
+    }
+
+    /// Time spent playing, excluding any time spent paused
+    pub fn elapsed(&self) -> web_time::Duration {
+        let now = if self.paused {
+            self.pause_start
+        } else {
+            web_time::Instant::now()
+        };
+
+        (now - self.start_time).saturating_sub(self.total_paused_duration)
+    }
+
     pub fn update(&mut self) -> State {
-        let now = web_time::Instant::now();
-        let elapsed = now - self.start_time;
+        if self.paused {
+            return State::Playing;
+        }
 
-        // TODO Implement this in terms of ticks to allow buzzer beaters
-        if elapsed > self.level.max_time {
-            return State::Defeat;
+        // Scale each frame's time step so changing the time scale doesn't jump the simulation
+        let elapsed = self.elapsed();
+        let frame_time = elapsed.saturating_sub(self.last_elapsed);
+        self.last_elapsed = elapsed;
+
+        // The clock stands still while a rewind plays back
+        if self.animate_rewind(frame_time.as_secs_f32()) {
+            return State::Playing;
         }
+        self.scaled_elapsed += frame_time.mul_f32(self.time_scale.max(0.0));
 
-        let target_ticks = (elapsed.as_secs_f32() * self.tick_rate).round() as u64;
-        while self.tick_counter < target_ticks {
-            self.tick_counter += 1;
-            self.update_physics();
+        // Run every tick up to the time limit before checking it, so that escaping on the last
+        // allowed tick still counts
+        let max_ticks = (self.level.max_time.as_secs_f32() * self.tick_rate).round() as u64;
+        let target_ticks = (self.scaled_elapsed.as_secs_f32() * self.tick_rate).round() as u64;
+        let penalty = std::mem::take(&mut self.respawn_penalty);
+        let penalty_ticks =
+            (penalty.as_secs_f32() * self.time_scale.max(0.0) * self.tick_rate).round() as u64;
+        let debt = target_ticks.saturating_sub(self.tick_counter);
+        if debt.saturating_sub(penalty_ticks) > self.tick_rate as u64 {
+            self.tick_overruns += 1;
+        }
+        while self.tick_counter < target_ticks.min(max_ticks) {
+            self.tick();
             if self.has_escaped() {
                 return State::Victory;
             }
         }
 
+        if self.scaled_elapsed > self.level.max_time {
+            return State::Defeat;
+        }
+
         self.frame_counter += 1;
         State::Playing
     }
 
+    /// Fails on the first part of the simulation that has become infinite or NaN
+    pub fn check_finite(&self) -> Result<(), GameError> {
+        let body = &self.level.body;
+        let fields = [
+            ("body.angle", body.angle.is_finite()),
+            ("body.angular_velocity", body.angular_velocity.is_finite()),
+            (
+                "ball.center",
+                self.level.balls.iter().all(|ball| ball.center.is_finite()),
+            ),
+            (
+                "ball.velocity",
+                self.level
+                    .balls
+                    .iter()
+                    .all(|ball| ball.velocity.is_finite()),
+            ),
+        ];
+
+        match fields.into_iter().find(|(_, finite)| !finite) {
+            Some((field, _)) => Err(GameError::PhysicsNaN { field }),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs ticks without regard for the clock until `tick` is reached or a ball escapes
+    pub fn advance_to(&mut self, tick: u64) {
+        while self.tick_counter < tick && !self.has_escaped() {
+            self.tick();
+        }
+    }
+
     pub fn has_escaped(&self) -> bool {
-        let winding_number = compute_winding_number(
-            self.level.ball.center,
-            &self.level.body.shape_with_rotation_applied(),
-        );
+        if !self.level.escape_zones.is_empty() {
+            return self.escaped;
+        }
+
+        let shape = self.level.body.shape_with_rotation_applied();
+        self.level
+            .balls
+            .iter()
+            .any(|ball| compute_winding_number(ball.center, &shape) == 0)
+    }
+
+    pub fn view_transform(&self, canvas_rect: Rect) -> TSTransform {
+        // Define scaling factor so the body takes up 80% of the available space
+        let max_extent = self
+            .level
+            .body
+            .shape
+            .max_extent(self.level.body.center_of_rotation);
+
+        let left_top_radius = max_extent.min.to_vec2().length();
+        let bottom_right_radius = max_extent.max.to_vec2().length();
+        let radius = left_top_radius.max(bottom_right_radius);
+
+        let scale = 0.8 * canvas_rect.size().min_elem() / (2. * radius);
+
+        let translation = match self.level.balls.first() {
+            Some(ball) if self.level.camera_follow => {
+                canvas_rect.center().to_vec2() - ball.center.to_vec2() * scale
+            }
+            _ => canvas_rect.center().to_vec2(),
+        };
+
+        TSTransform {
+            scaling: scale,
+            translation,
+        }
+    }
+
+    /// The level's brake work budget, less its share of any rewinds
+    pub fn brake_budget(&self) -> f32 {
+        self.level.max_brake_work - self.rewind_work / 2.
+    }
+
+    pub fn boost_budget(&self) -> f32 {
+        self.level.max_boost_work - self.rewind_work / 2.
+    }
 
-        winding_number == 0
+    pub fn brake_work_remaining(&self) -> f32 {
+        (self.brake_budget() - self.input_work.brake).max(0.0)
+    }
+
+    pub fn boost_work_remaining(&self) -> f32 {
+        (self.boost_budget() - self.input_work.boost).max(0.0)
     }
 
     pub fn work_remaining(&self) -> f32 {
-        let work_spent = self.input_work.brake + self.input_work.boost;
-        (self.level.max_work - work_spent).max(0.0)
+        self.brake_work_remaining() + self.boost_work_remaining()
+    }
+
+    /// Star rating from 1 to 3, limited by whichever of time and work was used most
+    pub fn rating(&self) -> u8 {
+        let stars = |fraction: f32| {
+            if fraction <= 0.33 {
+                3
+            } else if fraction <= 0.66 {
+                2
+            } else {
+                1
+            }
+        };
+
+        let time_fraction = self.scaled_elapsed.as_secs_f32() / self.level.max_time.as_secs_f32();
+        let work_fraction = 1.0
+            - 0.5
+                * (self.input_work.brake_fraction(self.brake_budget())
+                    + self.input_work.boost_fraction(self.boost_budget()));
+
+        stars(time_fraction).min(stars(work_fraction))
+    }
+
+    pub fn brake_enabled(&self) -> bool {
+        self.speed_run_mode || self.brake_work_remaining() > 0.0
+    }
+
+    pub fn boost_enabled(&self) -> bool {
+        self.speed_run_mode || self.boost_work_remaining() > 0.0
     }
 
     pub fn inputs_enabled(&self) -> bool {
-        self.work_remaining() > 0.0
+        self.brake_enabled() || self.boost_enabled()
     }
 
-    fn input(&self) -> InputSet {
-        if self.inputs_enabled() {
-            self.level.input
+    fn raw_input(&self) -> InputSet {
+        let Some(replay) = &self.replay else {
+            return self.level.input;
+        };
+
+        let inputs = &replay.inputs;
+        let entry = match inputs.binary_search_by_key(&self.tick_counter, |(tick, _, _, _)| *tick) {
+            Ok(index) => inputs.get(index),
+            Err(index) => index.checked_sub(1).and_then(|index| inputs.get(index)),
+        };
+        let mut input = self.level.input;
+        if let Some((_, brake, boost, motor)) = entry {
+            input.brake.active = *brake;
+            input.boost.active = *boost;
+            input.motor.active = *motor;
         } else {
-            InputSet {
-                brake: Input {
-                    torque: 0.0,
-                    active: false,
-                },
-                boost: Input {
-                    torque: 0.0,
-                    active: false,
-                },
-                ..self.level.input
+            input.brake.active = false;
+            input.boost.active = false;
+        }
+        input
+    }
+
+    fn input(&self) -> InputSet {
+        let mut input = self.raw_input();
+        let disabled = Input {
+            torque: 0.0,
+            active: false,
+        };
+
+        if !self.brake_enabled() {
+            input.brake = disabled;
+        }
+        if !self.boost_enabled() {
+            input.boost = disabled;
+        }
+        input
+    }
+
+    fn tick(&mut self) {
+        if let Some(replay) = &self.replay {
+            let rewinds = replay
+                .rewinds
+                .iter()
+                .filter(|tick| **tick == self.tick_counter)
+                .count();
+            self.rewind_work += rewinds as f32 * self.level.rewind_cost;
+        }
+
+        self.tick_counter += 1;
+        self.fire_events();
+        self.update_physics();
+        self.tutorial_state
+            .advance(&self.level.tutorial_steps, self.tick_counter);
+
+        if self.tick_history.is_some() {
+            let snapshot = self.snapshot();
+            if let Some(history) = self.tick_history.as_mut() {
+                history.push(snapshot);
+            }
+        }
+    }
+
+    pub fn tutorial_message(&self) -> Option<&str> {
+        let display_ticks = (4.0 * self.tick_rate) as u64;
+        self.tutorial_state
+            .message(&self.level.tutorial_steps, self.tick_counter, display_ticks)
+    }
+
+    fn fire_events(&mut self) {
+        let tick_time = self.tick_counter as f32 * self.tick_dt;
+
+        while let Some(event) = self.level.events.get(self.events_fired) {
+            if event.trigger_time_secs > tick_time {
+                break;
             }
+
+            match event.action.clone() {
+                LevelAction::SetGravity(gravity) => self.level.gravity = gravity,
+                LevelAction::SetMaxBrakeWork(max_work) => self.level.max_brake_work = max_work,
+                LevelAction::SetMaxBoostWork(max_work) => self.level.max_boost_work = max_work,
+                LevelAction::SetAngularVelocityLimit(limit) => {
+                    self.level.body.angular_velocity_limit = Some(limit)
+                }
+                LevelAction::AddForceField(field) => self.level.force_fields.push(field),
+            }
+
+            self.events_fired += 1;
         }
     }
 
     fn update_physics(&mut self) {
-        let update_result = self.level.body.update(self.input(), self.tick_dt);
+        if self.record_inputs {
+            let raw_input = self.raw_input();
+            self.input_log.inputs.push((
+                self.tick_counter,
+                raw_input.brake.active,
+                raw_input.boost.active,
+                raw_input.motor.active,
+            ));
+        }
+
+        let input = self.input();
+        if input.brake.active {
+            self.tutorial_state
+                .notify(TutorialTrigger::OnFirstBrakePress);
+        }
+        if input.boost.active {
+            self.tutorial_state
+                .notify(TutorialTrigger::OnFirstBoostPress);
+        }
+
+        let angular_velocity = self.level.body.angular_velocity;
+        let update_result = self.level.body.update(input, self.tick_dt);
+        if input.brake.active && angular_velocity * self.level.body.angular_velocity < 0.0 {
+            self.stats.brake_reversals += 1;
+        }
         self.input_work += update_result.work;
         self.collision_list.iter_mut().for_each(|collision| {
-            collision.update(update_result.delta_angle);
+            collision.update(update_result.delta_angle, self.tick_dt);
         });
 
-        let ball_previous_position = self.level.ball.center;
-        self.level.ball.update(self.tick_dt, self.level.gravity);
+        self.level
+            .moving_platform
+            .iter_mut()
+            .for_each(|platform| platform.update(self.tick_dt));
+
+        let trail_interval = (self.tick_rate / TRAIL_POINTS_PER_SEC).max(1.0) as u64;
+        let record_trail = self.tick_counter % trail_interval == 0;
+
+        for index in 0..self.level.balls.len() {
+            let ball_previous_position = self.level.balls[index].center;
+            if record_trail {
+                self.level.balls[index].trail.push(ball_previous_position);
+            }
+            let acceleration = self.acceleration_at(ball_previous_position);
+            self.level.balls[index].update(self.tick_dt, acceleration);
+
+            self.handle_collisions(index, ball_previous_position);
+
+            if self.has_tunneled(index, ball_previous_position) {
+                self.respawn_ball(index);
+                continue;
+            }
+
+            let ball = &self.level.balls[index];
+            self.stats.distance_traveled += (ball.center - ball_previous_position).length();
+            self.stats.max_speed = self.stats.max_speed.max(ball.velocity.length());
 
-        self.handle_collisions(ball_previous_position);
+            if !self.escaped && self.has_crossed_escape_zone(index, ball_previous_position) {
+                self.escaped = true;
+            }
+        }
+
+        self.handle_ball_ball_collisions();
+        self.record_checkpoints();
+
+        self.stats.work_spent_brake = self.input_work.brake;
+        self.stats.work_spent_boost = self.input_work.boost;
+
+        let ticks_per_sec = (self.tick_rate.round() as u64).max(1);
+        if self.tick_counter % ticks_per_sec == 0 {
+            let time = self.tick_counter as f32 * self.tick_dt;
+            self.work_history
+                .push((time, self.input_work.brake + self.input_work.boost));
+        }
     }
 
-    fn detect_collisions(&self) -> Vec<collision::Collision> {
-        let ball = &self.level.ball;
-        let body = &self.level.body;
+    pub fn score_multiplier(&self) -> f32 {
+        (1.0 + self.combo as f32 * 0.1).min(3.0)
+    }
 
-        let shape = body.shape_with_rotation_applied();
+    fn register_collision(&mut self) {
+        let combo_window_ticks = (0.5 * self.tick_rate) as u64;
+        let is_combo = self
+            .last_collision_tick
+            .is_some_and(|tick| self.tick_counter - tick <= combo_window_ticks);
 
-        let line_segments = shape.all_segments();
+        self.combo = if is_combo { self.combo + 1 } else { 0 };
+        self.last_collision_tick = Some(self.tick_counter);
+        self.score += (self.score_multiplier() * 10.0).round() as u32;
+    }
 
-        // Determine which, if any, line segments the ball is colliding with
-        line_segments
-            .into_iter()
-            .filter_map(|segment| collision::detect_collision(segment, *ball))
-            .collect()
+    fn handle_ball_ball_collisions(&mut self) {
+        let balls = &mut self.level.balls;
+
+        for i in 0..balls.len() {
+            for j in i + 1..balls.len() {
+                let (left, right) = balls.split_at_mut(j);
+                let (a, b) = (&mut left[i], &mut right[0]);
+
+                let offset = b.center - a.center;
+                let distance = offset.length();
+                let penetration = a.radius + b.radius - distance;
+                if penetration <= 0.0 {
+                    continue;
+                }
+
+                let normal = if distance > 0.0 {
+                    offset / distance
+                } else {
+                    Vec2::X
+                };
+
+                // Equal masses exchange their velocity components along the line of centers, but
+                // only while approaching so overlapping balls don't stick together
+                let a_normal_speed = a.velocity.dot(normal);
+                let b_normal_speed = b.velocity.dot(normal);
+                if a_normal_speed > b_normal_speed {
+                    a.velocity += (b_normal_speed - a_normal_speed) * normal;
+                    b.velocity += (a_normal_speed - b_normal_speed) * normal;
+                }
+
+                a.center -= normal * penetration / 2.0;
+                b.center += normal * penetration / 2.0;
+            }
+        }
     }
 
-    fn handle_collisions(&mut self, ball_previous_position: Pos2) {
-        let collisions = self.detect_collisions();
+    /// Whether the ball left the shape this tick without passing through one of its gaps
+    fn has_tunneled(&self, index: usize, ball_previous_position: Pos2) -> bool {
+        let shape = self.level.body.shape_with_rotation_applied();
+        let ball_position = self.level.balls[index].center;
 
-        if collisions.is_empty() {
-            return;
+        if compute_winding_number(ball_position, &shape) != 0
+            || compute_winding_number(ball_previous_position, &shape) == 0
+        {
+            return false;
         }
 
-        let aggregate_normal = collisions
-            .iter()
-            .map(|collision| collision.normal)
-            .fold(Vec2::ZERO, |acc, n| acc + n)
-            .normalized();
+        !shape
+            .openings()
+            .into_iter()
+            .any(|opening| segments_intersect((ball_previous_position, ball_position), opening))
+    }
 
-        self.level.ball.velocity = self.level.ball.velocity
-            - 2.0 * self.level.ball.velocity.dot(aggregate_normal) * aggregate_normal;
+    /// Puts the ball back at its starting state, at the cost of 10% of the work budget and five
+    /// seconds of game time
+    pub fn respawn_ball(&mut self, index: usize) {
+        self.level.balls[index] = self.ball_initial_state[index].clone();
+        self.level.max_brake_work *= 0.9;
+        self.level.max_boost_work *= 0.9;
 
-        let delta_angle = -self.level.body.angular_velocity * self.tick_dt;
+        // Moving the start back keeps the clock and the tick counter in step, as the next update
+        // simulates the penalty like any other time
+        self.start_time -= RESPAWN_PENALTY;
+        self.respawn_penalty += RESPAWN_PENALTY;
+    }
 
-        // Pick the collision that is closest for the shape and ball's previous position
-        let closest_collision = collisions
+    pub fn acceleration_at(&self, point: Pos2) -> Vec2 {
+        let wells = self
+            .level
+            .gravity_wells
             .iter()
-            .map(|collision| {
-                let rotated = collision.rotate(delta_angle, self.level.body.center_of_rotation);
-                (collision, rotated.point)
-            })
-            .min_by(|a, b| {
-                let dist_a = (a.1 - ball_previous_position).length();
-                let dist_b = (b.1 - ball_previous_position).length();
+            .map(|well| well.acceleration_at(point));
+        let fields = self
+            .level
+            .force_fields
+            .iter()
+            .map(|field| field.acceleration_at(point));
 
-                dist_a.partial_cmp(&dist_b).unwrap()
-            })
-            .unwrap()
-            .0;
+        wells
+            .chain(fields)
+            .fold(self.level.gravity, |acc, a| acc + a)
+    }
 
-        self.level.ball.center =
-            closest_collision.point + closest_collision.normal * self.level.ball.radius;
+    /// How close the nearest ball is to getting out, from 0 to 1. Measured against the escape
+    /// zones if the level has any, otherwise against the gaps in the shape.
+    pub fn escape_progress(&self) -> f32 {
+        if self.has_escaped() {
+            return 1.0;
+        }
 
-        let rotating_collisions = collisions.into_iter().map(|collision| {
-            rotating::Collision::new(collision, self.level.body.center_of_rotation)
-        });
+        let body = &self.level.body;
+        let exits: Vec<Segment> = if self.level.escape_zones.is_empty() {
+            body.shape_with_rotation_applied().openings()
+        } else {
+            self.rotated_escape_zones()
+                .map(|zone| zone.segment())
+                .collect()
+        };
 
-        self.collision_list.extend(rotating_collisions);
-    }
-}
+        let bounding_radius = body.shape.max_extent(body.center_of_rotation).width() / 2.;
+        if exits.is_empty() || bounding_radius <= 0.0 {
+            return 0.0;
+        }
 
-impl Drawable for Game {
-    fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
+        let closest_distance = self
+            .level
+            .balls
+            .iter()
+            .flat_map(|ball| {
+                exits
+                    .iter()
+                    .map(|exit| (distance_to_segment(ball.center, *exit) - ball.radius).max(0.0))
+            })
+            .fold(f32::INFINITY, f32::min);
+
+        (1.0 - closest_distance / bounding_radius).clamp(0.0, 1.0)
+    }
+
+    fn rotated_escape_zones(&self) -> impl Iterator<Item = EscapeZone> + '_ {
+        let body = &self.level.body;
+        self.level
+            .escape_zones
+            .iter()
+            .map(|zone| zone.rotate(body.angle, body.center_of_rotation))
+    }
+
+    fn rotated_checkpoints(&self) -> impl Iterator<Item = Checkpoint> + '_ {
+        let body = &self.level.body;
+        self.level
+            .checkpoints
+            .iter()
+            .map(|checkpoint| checkpoint.rotate(body.angle, body.center_of_rotation))
+    }
+
+    fn record_checkpoints(&mut self) {
+        let time = self.tick_counter as f32 * self.tick_dt;
+        let reached: Vec<bool> = self
+            .rotated_checkpoints()
+            .map(|checkpoint| {
+                self.level
+                    .balls
+                    .iter()
+                    .any(|ball| checkpoint.contains(ball.center))
+            })
+            .collect();
+
+        for (split, reached) in self.checkpoint_times.iter_mut().zip(reached) {
+            if split.is_none() && reached {
+                *split = Some(time);
+            }
+        }
+    }
+
+    /// Label and time of each checkpoint reached so far, in the level's order
+    pub fn split_times(&self) -> impl Iterator<Item = (&str, f32)> + '_ {
+        self.level
+            .checkpoints
+            .iter()
+            .zip(&self.checkpoint_times)
+            .filter_map(|(checkpoint, split)| split.map(|split| (checkpoint.label.as_str(), split)))
+    }
+
+    fn has_crossed_escape_zone(&self, index: usize, ball_previous_position: Pos2) -> bool {
+        let ball_position = self.level.balls[index].center;
+        let crossed = self
+            .rotated_escape_zones()
+            .any(|zone| zone.is_crossed_by(ball_previous_position, ball_position));
+
+        // Only count crossings that leave the shape, not ones heading back in
+        crossed
+            && compute_winding_number(
+                ball_position,
+                &self.level.body.shape_with_rotation_applied(),
+            ) == 0
+    }
+
+    pub fn draw_checkpoints(&self, painter: &egui::Painter, transform: TSTransform) {
+        for (checkpoint, split) in self.rotated_checkpoints().zip(&self.checkpoint_times) {
+            checkpoint.draw(painter, transform, split.is_some());
+        }
+    }
+
+    /// Draws each ball's trail in the colour of the ball it follows
+    pub fn draw_trails(&self, painter: &egui::Painter, transform: TSTransform) {
+        let fallback = painter.ctx().style().visuals.error_fg_color;
+        for ball in &self.level.balls {
+            ball.draw_trail(painter, transform, ball.color.unwrap_or(fallback));
+        }
+    }
+
+    pub fn draw_background(&self, painter: &egui::Painter, canvas_rect: Rect) {
+        if let Some(background_color) = self.level.background_color {
+            painter.rect_filled(canvas_rect, 0.0, background_color);
+        }
+    }
+
+    pub fn draw_escape_zones(&self, painter: &egui::Painter, transform: TSTransform) {
+        let elapsed = (web_time::Instant::now() - self.start_time).as_secs_f32();
+
+        self.rotated_escape_zones().for_each(|zone| {
+            let ball_nearby = self
+                .level
+                .balls
+                .iter()
+                .any(|ball| zone.distance_to(ball.center) < 2.0 * ball.radius);
+            let opacity = if ball_nearby {
+                0.75 + 0.25 * (elapsed * 4.0 * std::f32::consts::PI).sin()
+            } else {
+                0.5
+            };
+            zone.draw(painter, transform, opacity);
+        });
+    }
+
+    /// Checks are sequential on purpose: a full 64-segment check takes about 1 µs, far less than
+    /// handing work to another thread costs
+    pub fn detect_collisions(&self, ball: &Ball) -> Vec<collision::Collision> {
+        let body = &self.level.body;
+
+        // Most of the time the ball is nowhere near a wall, so skip rotating and checking them
+        let line_segments = if self.body_bounds.may_collide(body.center_of_rotation, ball) {
+            body.shape_with_rotation_applied().all_segments()
+        } else {
+            Vec::new()
+        };
+
+        // Determine which, if any, line segments the ball is colliding with
+        let body_collisions = line_segments
+            .into_iter()
+            .filter_map(|segment| collision::detect_collision(segment, ball));
+
+        let platform_collisions = self.level.moving_platform.iter().flat_map(|platform| {
+            let velocity = platform.velocity();
+            platform
+                .shape_at_position()
+                .all_segments()
+                .into_iter()
+                .filter_map(move |segment| {
+                    collision::detect_collision(segment, ball)
+                        .map(|collision| collision.with_surface_velocity(velocity))
+                })
+        });
+
+        body_collisions.chain(platform_collisions).collect()
+    }
+
+    fn handle_collisions(&mut self, index: usize, ball_previous_position: Pos2) {
+        let collisions = self.detect_collisions(&self.level.balls[index]);
+
+        if collisions.is_empty() {
+            return;
+        }
+
+        self.stats.total_collisions += 1;
+        self.register_collision();
+        self.tutorial_state
+            .notify(TutorialTrigger::OnFirstCollision);
+
+        let aggregate_normal = collisions
+            .iter()
+            .map(|collision| collision.normal)
+            .fold(Vec2::ZERO, |acc, n| acc + n)
+            .normalized();
+
+        // Reflect the velocity relative to the surface so moving platforms transfer momentum
+        let surface_velocity = collisions
+            .iter()
+            .map(|collision| collision.surface_velocity)
+            .fold(Vec2::ZERO, |acc, v| acc + v)
+            / collisions.len() as f32;
+
+        let ball = &mut self.level.balls[index];
+        let relative_velocity = ball.velocity - surface_velocity;
+        let velocity_at_impact = relative_velocity.dot(aggregate_normal).abs();
+        ball.velocity = surface_velocity + relative_velocity
+            - 2.0 * relative_velocity.dot(aggregate_normal) * aggregate_normal;
+
+        let delta_angle = -self.level.body.angular_velocity * self.tick_dt;
+
+        // Pick the collision that is closest for the shape and ball's previous position
+        let closest_collision = collisions
+            .iter()
+            .map(|collision| {
+                let rotated = collision.rotate(delta_angle, self.level.body.center_of_rotation);
+                (collision, rotated.point)
+            })
+            .min_by(|a, b| {
+                let dist_a = (a.1 - ball_previous_position).length();
+                let dist_b = (b.1 - ball_previous_position).length();
+
+                dist_a.partial_cmp(&dist_b).unwrap()
+            })
+            .unwrap()
+            .0;
+
+        let ball = &mut self.level.balls[index];
+        ball.center = closest_collision.point + closest_collision.normal * ball.radius;
+
+        let rotating_collisions = collisions.into_iter().map(|collision| {
+            rotating::Collision::new(
+                collision.with_velocity_at_impact(velocity_at_impact),
+                self.level.body.center_of_rotation,
+            )
+        });
+
+        self.collision_list.extend(rotating_collisions);
+    }
+}
+
+impl Drawable for Game {
+    fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
+        self.level.gravity_wells.iter().for_each(|well| {
+            well.draw(ctx, painter, transform);
+        });
+        self.level.force_fields.iter().for_each(|field| {
+            field.draw(ctx, painter, transform);
+        });
         self.level.body.draw(ctx, painter, transform);
+        self.draw_escape_zones(painter, transform);
+        self.draw_checkpoints(painter, transform);
+        self.level.moving_platform.iter().for_each(|platform| {
+            platform.draw(ctx, painter, transform);
+        });
         self.collision_list.iter().for_each(|collision| {
             collision.draw(ctx, painter, transform);
         });
-        self.level.ball.draw(ctx, painter, transform);
+        self.level.balls.iter().for_each(|ball| {
+            ball.draw(ctx, painter, transform);
+        });
+    }
+
+    /// A complete SVG document of the body, platforms and balls, framed around the body
+    fn draw_to_svg(&self) -> String {
+        let body = &self.level.body;
+        let extent = body.shape.max_extent(body.center_of_rotation);
+        let view_box = extent.expand(0.1 * extent.width());
+        let line_width = 0.005 * view_box.width();
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" \
+             fill=\"none\" stroke=\"black\" stroke-width=\"{}\">\n",
+            view_box.min.x,
+            view_box.min.y,
+            view_box.width(),
+            view_box.height(),
+            line_width
+        );
+        svg += &body.draw_to_svg();
+        for platform in &self.level.moving_platform {
+            svg += &platform.draw_to_svg();
+        }
+        for ball in &self.level.balls {
+            svg += &ball.draw_to_svg();
+        }
+        svg += "</svg>\n";
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        field::{ForceField, GravityWell},
+        level::LevelEvent,
+        rng::XorShift,
+        tutorial::TutorialStep,
+    };
+    use egui::Color32;
+
+    const PROPERTY_CASES: u64 = 200;
+
+    /// A game with the body at a random angle and the ball overlapping a random wall, heading
+    /// into it
+    fn wall_case(rng: &mut XorShift) -> Game {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+
+        let body = &mut game.level.body;
+        body.angle = rng.range(0.0, std::f32::consts::TAU);
+        body.angular_velocity = rng.range(-5.0, 5.0);
+
+        let shape = body.shape_with_rotation_applied();
+        let segments = shape.all_segments();
+        let (start, end) = segments[(rng.next_f32() * segments.len() as f32) as usize];
+        let along = start + rng.range(0.1, 0.9) * (end - start);
+        let tangent = (end - start).normalized();
+        let inward = (body.center_of_rotation - along).normalized();
+
+        let ball = &mut game.level.balls[0];
+        ball.center = along + rng.range(0.2, 0.9) * ball.radius * inward;
+        ball.velocity = rng.range(0.5, 5.0) * -inward + rng.range(-3.0, 3.0) * tangent;
+        game
+    }
+
+    #[test]
+    fn test_property_collisions_push_ball_out_of_walls() {
+        let mut rng = XorShift::new(0x5eed);
+        for _ in 0..PROPERTY_CASES {
+            let mut game = wall_case(&mut rng);
+            let previous = game.level.balls[0].center;
+            game.handle_collisions(0, previous);
+            assert!(game.stats.total_collisions > 0);
+
+            let ball = &game.level.balls[0];
+            let shape = game.level.body.shape_with_rotation_applied();
+            let nearest = shape
+                .all_segments()
+                .into_iter()
+                .map(|segment| distance_to_segment(ball.center, segment))
+                .fold(f32::INFINITY, f32::min);
+            assert!(
+                nearest >= ball.radius - 1e-4,
+                "ball at {:?} is {nearest} from a wall",
+                ball.center
+            );
+        }
+    }
+
+    #[test]
+    fn test_property_bounds_never_skip_collisions() {
+        let mut rng = XorShift::new(0xb0b);
+        for _ in 0..PROPERTY_CASES {
+            let mut game = wall_case(&mut rng);
+            let ball = &mut game.level.balls[0];
+            ball.center = Pos2::new(rng.range(-1.2, 1.2), rng.range(-1.2, 1.2));
+            let ball = ball.clone();
+
+            let body = &game.level.body;
+            if !game.body_bounds.may_collide(body.center_of_rotation, &ball) {
+                let shape = body.shape_with_rotation_applied();
+                assert!(shape
+                    .all_segments()
+                    .into_iter()
+                    .all(|segment| collision::detect_collision(segment, &ball).is_none()));
+            }
+        }
+
+        let game = Game::new(Level::simple_polygon(6), 1024.);
+        let center = game.level.body.center_of_rotation;
+        let ball = &game.level.balls[0];
+        assert!(!game.body_bounds.may_collide(center, ball));
+    }
+
+    #[test]
+    fn test_property_collisions_conserve_energy() {
+        let mut rng = XorShift::new(0xe4e7);
+        for _ in 0..PROPERTY_CASES {
+            let mut game = wall_case(&mut rng);
+            game.level.gravity = Vec2::ZERO;
+
+            let before = game.level.balls[0].velocity.length_sq();
+            let previous = game.level.balls[0].center;
+            game.handle_collisions(0, previous);
+            assert!(game.stats.total_collisions > 0);
+            let after = game.level.balls[0].velocity.length_sq();
+
+            assert!(
+                (after - before).abs() <= 0.01 * before,
+                "kinetic energy went from {before} to {after}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_property_state_stays_finite() {
+        let mut rng = XorShift::new(0xf1417e);
+        for _ in 0..PROPERTY_CASES {
+            let mut game = wall_case(&mut rng);
+            for _ in 0..100 {
+                game.level.input.brake.active = rng.next_f32() < 0.3;
+                game.level.input.boost.active = rng.next_f32() < 0.3;
+                game.tick();
+            }
+            assert!(game.stats.total_collisions > 0);
+
+            let ball = &game.level.balls[0];
+            assert!(ball.center.is_finite() && ball.velocity.is_finite());
+            assert!(game.level.body.angle.is_finite());
+        }
+    }
+
+    #[test]
+    fn test_same_inputs_give_same_result() {
+        let level = Level::simple_polygon(6);
+        let mut games = [Game::new(level.clone(), 1024.), Game::new(level, 1024.)];
+
+        let mut rng = XorShift::new(0xde7e);
+        for tick in 1..=1000 {
+            let mut input = games[0].level.input;
+            input.brake.active = rng.next_f32() < 0.2;
+            input.boost.active = rng.next_f32() < 0.2;
+
+            for game in &mut games {
+                game.tick_counter = tick;
+                game.level.input = input;
+                game.update_physics();
+            }
+
+            if tick % 100 == 0 {
+                let [a, b] = &games;
+                for (ball_a, ball_b) in a.level.balls.iter().zip(&b.level.balls) {
+                    assert_eq!(ball_a.center, ball_b.center, "diverged at tick {tick}");
+                    assert_eq!(ball_a.velocity, ball_b.velocity, "diverged at tick {tick}");
+                }
+                assert_eq!(
+                    a.level.body.angle, b.level.body.angle,
+                    "diverged at tick {tick}"
+                );
+                assert_eq!(a.level.body.angular_velocity, b.level.body.angular_velocity);
+            }
+        }
+    }
+
+    #[test]
+    fn test_initial_body_angle() {
+        let mut level = Level::simple_polygon(6);
+        level.initial_body_angle = std::f32::consts::FRAC_PI_4;
+
+        let game = Game::new(level, 1024.);
+        assert_eq!(game.level.body.angle, std::f32::consts::FRAC_PI_4);
+    }
+
+    #[test]
+    fn test_camera_follow_centers_ball() {
+        let mut level = Level::simple_polygon(6);
+        level.camera_follow = true;
+
+        let mut game = Game::new(level, 1024.);
+        let canvas_rect = Rect::from_min_size(Pos2::new(10., 20.), Vec2::new(400., 300.));
+
+        for center in [Pos2::ZERO, Pos2::new(0.5, -0.3), Pos2::new(-0.8, 0.1)] {
+            game.level.balls[0].center = center;
+            let transform = game.view_transform(canvas_rect);
+            let drawn = transform.mul_pos(game.level.balls[0].center);
+
+            assert!((drawn - canvas_rect.center()).length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_first_collision_tutorial_fires_once() {
+        let mut level = Level::simple_polygon(6);
+        level.tutorial_steps = vec![TutorialStep {
+            trigger: TutorialTrigger::OnFirstCollision,
+            message: "Bounce!".to_owned(),
+        }];
+
+        let mut game = Game::new(level, 1024.);
+        let mut fired_at = Vec::new();
+
+        for _ in 0..2048 {
+            let before = game.tutorial_state.next_step;
+            game.tick();
+            if game.tutorial_state.next_step != before {
+                fired_at.push(game.tick_counter);
+            }
+        }
+
+        assert_eq!(fired_at.len(), 1);
+        assert!(game.collision_list.len() > 1);
+        assert_eq!(game.tutorial_state.active_step, Some((0, fired_at[0])));
+    }
+
+    #[test]
+    fn test_gravity_wells_sum() {
+        let mut level = Level::simple_polygon(6);
+        level.gravity = Vec2::ZERO;
+        level.gravity_wells = vec![
+            GravityWell {
+                center: Pos2::new(0.5, 0.0),
+                strength: 1.0,
+                radius: 1.0,
+            },
+            GravityWell {
+                center: Pos2::new(0.0, 0.5),
+                strength: 2.0,
+                radius: 1.0,
+            },
+        ];
+
+        let game = Game::new(level, 1024.);
+        let acceleration = game.acceleration_at(Pos2::ZERO);
+
+        assert!((acceleration - Vec2::new(4.0, 8.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_force_field_velocity_increment() {
+        let mut level = Level::simple_polygon(6);
+        level.gravity = Vec2::ZERO;
+        level.body.angular_velocity = 0.0;
+        level.input.motor.active = false;
+        level.force_fields = vec![ForceField {
+            region: Rect::from_center_size(Pos2::ZERO, Vec2::splat(0.5)),
+            force: Vec2::new(2.0, 0.0),
+        }];
+
+        let mut game = Game::new(level, 1024.);
+        game.tick();
+
+        let expected = Vec2::new(2.0, 0.0) * game.tick_dt;
+        assert!((game.level.balls[0].velocity - expected).length() < 1e-6);
+    }
+
+    #[test]
+    fn test_background_color_fills_canvas() {
+        let mut level = Level::simple_polygon(6);
+        level.background_color = Some(egui::Color32::BLUE);
+        let game = Game::new(level, 1024.);
+
+        let ctx = egui::Context::default();
+        let canvas_rect = Rect::from_min_size(Pos2::ZERO, Vec2::new(100., 100.));
+        let output = ctx.run(egui::RawInput::default(), |ctx| {
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            game.draw_background(&painter, canvas_rect);
+        });
+
+        let filled = output.shapes.iter().any(|clipped| match &clipped.shape {
+            egui::Shape::Rect(rect) => rect.rect == canvas_rect && rect.fill == egui::Color32::BLUE,
+            _ => false,
+        });
+        assert!(filled);
+    }
+
+    #[test]
+    fn test_pause_excludes_paused_time() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+        game.start_time -= web_time::Duration::from_secs(3);
+
+        game.update();
+        let ticks_before_pause = game.tick_counter;
+        assert!(ticks_before_pause > 0);
+
+        game.pause();
+        game.update();
+        assert_eq!(game.tick_counter, ticks_before_pause);
+
+        game.resume();
+        game.total_paused_duration = web_time::Duration::from_secs(2);
+
+        let wall_clock = (web_time::Instant::now() - game.start_time).as_secs_f32();
+        let elapsed = game.elapsed().as_secs_f32();
+        assert!((wall_clock - elapsed - 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_no_ticks_while_paused() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+        game.start_time -= web_time::Duration::from_secs_f32(100. / 1024.);
+        game.update();
+        let ticks_before_pause = game.tick_counter;
+        assert!(ticks_before_pause >= 100);
+
+        game.pause();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        game.update();
+        assert_eq!(game.tick_counter, ticks_before_pause);
+
+        // Only the time since resuming counts, not the 50 ms spent paused
+        game.resume();
+        game.update();
+        assert!(game.tick_counter - ticks_before_pause < 10);
+    }
+
+    #[test]
+    fn test_time_scale() {
+        let ticks_after_one_second = |time_scale: f32| {
+            let mut game = Game::new(Level::simple_polygon(6), 1024.);
+            game.time_scale = time_scale;
+            game.start_time -= web_time::Duration::from_secs(1);
+            game.update();
+            game.tick_counter as f32
+        };
+
+        let normal = ticks_after_one_second(1.0);
+        let slow = ticks_after_one_second(0.5);
+
+        assert!((slow / normal - 0.5).abs() < 0.01);
+        assert_eq!(ticks_after_one_second(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_rating() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+        let max_time = game.level.max_time;
+        let spend = |game: &mut Game, fraction: f32| {
+            game.input_work.brake = fraction * game.level.max_brake_work;
+            game.input_work.boost = fraction * game.level.max_boost_work;
+        };
+
+        game.scaled_elapsed = max_time.mul_f32(0.2);
+        spend(&mut game, 0.2);
+        assert_eq!(game.rating(), 3);
+
+        spend(&mut game, 0.5);
+        assert_eq!(game.rating(), 2);
+
+        // Only using the brake counts for half as much as using both
+        game.input_work.boost = 0.0;
+        game.input_work.brake = game.level.max_brake_work;
+        assert_eq!(game.rating(), 2);
+
+        game.scaled_elapsed = max_time.mul_f32(0.8);
+        spend(&mut game, 0.8);
+        assert_eq!(game.rating(), 1);
+    }
+
+    #[test]
+    fn test_run_stats_counts_collisions() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+
+        for _ in 0..5 {
+            // Place the ball against the bottom edge, moving into it
+            game.level.balls[0].center = Pos2::new(0.0, 0.83);
+            game.level.balls[0].velocity = Vec2::new(0.0, 1.0);
+            game.tick();
+        }
+
+        assert_eq!(game.stats.total_collisions, 5);
+        assert!(game.stats.distance_traveled > 0.0);
+        assert!(game.stats.max_speed >= 1.0);
+    }
+
+    #[test]
+    fn test_replay_matches_recording() {
+        let level = Level::simple_polygon(6);
+
+        let mut recorded = Game::new(level.clone(), 1024.);
+        recorded.record_inputs = true;
+        for tick in 0..500 {
+            recorded.level.input.brake.active = (tick / 60) % 3 == 0;
+            recorded.level.input.boost.active = (tick / 45) % 2 == 0;
+            recorded.level.input.motor.active = (tick / 100) % 2 == 0;
+            recorded.tick();
+        }
+
+        let mut replayed = Game::new(level, 1024.).with_replay(recorded.input_log.clone());
+        for _ in 0..500 {
+            replayed.tick();
+        }
+
+        assert_eq!(recorded.input_log.inputs.len(), 500);
+        assert!((recorded.level.balls[0].center - replayed.level.balls[0].center).length() < 1e-5);
+        assert_eq!(recorded.level.body.angle, replayed.level.body.angle);
+    }
+
+    #[test]
+    fn test_multiple_balls_collide_independently() {
+        let mut level = Level::simple_polygon(6);
+        level.balls = vec![Ball::default(), Ball::default()];
+
+        let mut game = Game::new(level, 1024.);
+
+        // One ball against the bottom edge, the other against the top edge
+        game.level.balls[0].center = Pos2::new(0.0, 0.83);
+        game.level.balls[0].velocity = Vec2::new(0.0, 1.0);
+        game.level.balls[1].center = Pos2::new(0.0, -0.83);
+        game.level.balls[1].velocity = Vec2::new(0.0, -1.0);
+        game.tick();
+
+        assert_eq!(game.stats.total_collisions, 2);
+        assert!(game.level.balls[0].velocity.y < 0.0);
+        assert!(game.level.balls[1].velocity.y > 0.0);
+        assert!(game.level.balls[0].center.y < 0.83);
+        assert!(game.level.balls[1].center.y > -0.83);
+    }
+
+    #[test]
+    fn test_head_on_ball_collision_swaps_velocities() {
+        let mut level = Level::simple_polygon(6);
+        level.balls = vec![
+            Ball {
+                center: Pos2::new(-0.04, 0.0),
+                velocity: Vec2::new(1.0, 0.0),
+                ..Default::default()
+            },
+            Ball {
+                center: Pos2::new(0.04, 0.0),
+                velocity: Vec2::new(-1.0, 0.0),
+                ..Default::default()
+            },
+        ];
+
+        let mut game = Game::new(level, 1024.);
+        game.handle_ball_ball_collisions();
+
+        let [a, b] = &game.level.balls[..] else {
+            panic!("Expected two balls");
+        };
+        assert_eq!(a.velocity, Vec2::new(-1.0, 0.0));
+        assert_eq!(b.velocity, Vec2::new(1.0, 0.0));
+        assert!((b.center - a.center).length() >= a.radius + b.radius - 1e-6);
+    }
+
+    #[test]
+    fn test_separated_balls_are_unchanged() {
+        let mut level = Level::simple_polygon(6);
+        level.balls = vec![
+            Ball {
+                center: Pos2::new(-0.5, 0.0),
+                velocity: Vec2::new(1.0, 0.0),
+                ..Default::default()
+            },
+            Ball {
+                center: Pos2::new(0.5, 0.0),
+                velocity: Vec2::new(-1.0, 0.0),
+                ..Default::default()
+            },
+        ];
+
+        let mut game = Game::new(level.clone(), 1024.);
+        game.handle_ball_ball_collisions();
+
+        for (ball, original) in game.level.balls.iter().zip(&level.balls) {
+            assert_eq!(ball.center, original.center);
+            assert_eq!(ball.velocity, original.velocity);
+        }
+    }
+
+    #[test]
+    fn test_combo_multiplier() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+
+        for tick in [100, 300, 500] {
+            game.tick_counter = tick;
+            game.register_collision();
+        }
+        assert_eq!(game.combo, 2);
+        assert!((game.score_multiplier() - 1.2).abs() < 1e-6);
+        assert_eq!(game.score, 10 + 11 + 12);
+
+        // A collision more than 500 ms later resets the combo
+        game.tick_counter = 2000;
+        game.register_collision();
+        assert_eq!(game.combo, 0);
+        assert_eq!(game.score, 33 + 10);
+    }
+
+    #[test]
+    fn test_speed_run_mode_ignores_work_budget() {
+        let mut level = Level::simple_polygon(6);
+        level.is_practice = true;
+
+        let mut game = Game::new(level, 1024.);
+        game.input_work.brake = 2.0 * game.level.max_brake_work;
+        game.input_work.boost = 2.0 * game.level.max_boost_work;
+
+        assert!(game.speed_run_mode);
+        assert_eq!(game.work_remaining(), 0.0);
+        assert!(game.brake_enabled());
+        assert!(game.boost_enabled());
+    }
+
+    #[test]
+    fn test_respawn_ball() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+        let max_brake_work = game.level.max_brake_work;
+
+        game.level.balls[0].center = Pos2::new(0.3, 0.4);
+        game.level.balls[0].velocity = Vec2::new(1.0, 1.0);
+        let start_time = game.start_time;
+        game.respawn_ball(0);
+
+        assert_eq!(
+            game.level.balls[0].center,
+            game.ball_initial_state[0].center
+        );
+        assert_eq!(
+            game.level.balls[0].velocity,
+            game.ball_initial_state[0].velocity
+        );
+        assert_eq!(start_time - game.start_time, RESPAWN_PENALTY);
+        assert!((game.level.max_brake_work - 0.9 * max_brake_work).abs() < 1e-6);
+
+        // The penalty is simulated on the next update, so the timer and ticks still agree
+        game.update();
+        let timer = game.scaled_elapsed.as_secs_f32();
+        assert!(timer >= 5.0, "timer shows {timer} s");
+        assert!((game.tick_counter as f32 * game.tick_dt - timer).abs() <= game.tick_dt);
+        assert_eq!(game.tick_overruns, 0);
+    }
+
+    #[test]
+    fn test_tunneling_through_wall_respawns() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+
+        // Fast enough to jump clean over the bottom edge in a single tick
+        game.level.balls[0].center = Pos2::new(0.0, 0.8);
+        game.level.balls[0].velocity = Vec2::new(0.0, 200.0);
+        game.tick();
+
+        assert_eq!(
+            game.level.balls[0].center,
+            game.ball_initial_state[0].center
+        );
+        assert!(!game.has_escaped());
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.).with_history(64);
+
+        let mut centers = vec![game.level.balls[0].center];
+        for _ in 0..20 {
+            game.tick();
+            centers.push(game.level.balls[0].center);
+        }
+
+        assert_eq!(game.rollback(10), Ok(()));
+        assert_eq!(game.tick_counter, 10);
+        assert_eq!(game.level.balls[0].center, centers[10]);
+
+        assert_eq!(game.rollback(11), Err(RollbackError::InsufficientHistory));
+        assert_eq!(
+            Game::new(Level::simple_polygon(6), 1024.).rollback(1),
+            Err(RollbackError::HistoryDisabled)
+        );
+    }
+
+    #[test]
+    fn test_rollback_restores_events_and_platforms() {
+        let mut level = Level::simple_polygon(6);
+        level.events.push(LevelEvent {
+            trigger_time_secs: 0.5,
+            action: LevelAction::SetGravity(Vec2::new(0.0, -9.81)),
+        });
+        level.moving_platform.push(crate::platform::MovingPlatform {
+            shape: crate::shape::Shape::regular_polygon(4, 0.1, Pos2::ZERO),
+            waypoints: vec![Pos2::new(-0.4, 0.5), Pos2::new(0.4, 0.5)],
+            speed: 1.0,
+            progress: 0.0,
+            current_waypoint: 0,
+        });
+
+        let mut game = Game::new(level.clone(), 1024.).with_history(2048);
+        game.advance_to(1500);
+        assert_eq!(game.events_fired, 1);
+
+        let assert_same = |game: &Game, fresh: &Game| {
+            assert_eq!(game.tick_counter, fresh.tick_counter);
+            assert_eq!(game.level.balls, fresh.level.balls);
+            assert_eq!(game.level.body.angle, fresh.level.body.angle);
+            assert_eq!(game.level.gravity, fresh.level.gravity);
+            assert_eq!(game.events_fired, fresh.events_fired);
+            assert_eq!(game.level.moving_platform, fresh.level.moving_platform);
+            assert_eq!(game.stats.total_collisions, fresh.stats.total_collisions);
+            assert_eq!(game.stats.distance_traveled, fresh.stats.distance_traveled);
+            assert_eq!(game.score, fresh.score);
+        };
+
+        // Back to before the event fired, then on to the same tick again
+        let mut fresh = Game::new(level, 1024.);
+        game.rollback(1200).unwrap();
+        fresh.advance_to(300);
+        assert_same(&game, &fresh);
+
+        game.advance_to(1500);
+        fresh.advance_to(1500);
+        assert_same(&game, &fresh);
+        assert!(game.stats.total_collisions > 0);
+        assert_eq!(game.events_fired, 1);
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.).with_history(4096);
+        game.record_inputs = true;
+        let max_boost_work = game.level.max_boost_work;
+        assert!(!game.can_rewind());
+
+        for _ in 0..3 * 1024 {
+            game.tick();
+        }
+        assert!(game.can_rewind());
+
+        let tick_counter = game.tick_counter;
+        assert_eq!(game.rewind(), Ok(()));
+        assert_eq!(game.tick_counter, tick_counter - 2 * 1024);
+        assert_eq!(game.level.max_boost_work, max_boost_work);
+        assert_eq!(
+            game.boost_budget(),
+            max_boost_work - game.level.rewind_cost / 2.
+        );
+        assert!(game.rewind_animation.is_some());
+        assert!(!game.can_rewind());
+
+        // A replay of the run pays for the rewind at the same point
+        game.level.input.boost.active = true;
+        game.advance_to(3 * 1024);
+        let mut replayed =
+            Game::new(Level::simple_polygon(6), 1024.).with_replay(game.input_log.clone());
+        replayed.advance_to(3 * 1024);
+        assert_eq!(replayed.rewind_work, game.rewind_work);
+        assert_eq!(replayed.work_remaining(), game.work_remaining());
+        assert_eq!(replayed.level.balls, game.level.balls);
+    }
+
+    #[test]
+    fn test_find_escape() {
+        let game = Game::new(Level::tutorial(6), 1024.);
+        let ticks = game.find_escape(30 * 1024);
+        assert!(ticks.is_some());
+
+        let closed = Game::new(Level::simple_polygon(6), 1024.);
+        assert_eq!(closed.find_escape(30 * 1024), None);
+    }
+
+    #[test]
+    fn test_optimal_escape_time() {
+        let game = Game::new(Level::tutorial(6), 1024.);
+        let time = game.optimal_escape_time().expect("No escape found");
+        assert!(time > 0.0 && time <= 10.0);
+
+        let closed = Game::new(Level::simple_polygon(6), 1024.);
+        assert_eq!(closed.optimal_escape_time(), None);
+
+        // The tutorial takes hundreds of branches, so a tiny budget runs out first
+        let progress = |game: &Game| (game.escape_progress() * 1e6) as u64;
+        assert_eq!(game.search_for_escape(10 * 1024, 1e-2, 16, progress), None);
+    }
+
+    #[test]
+    fn test_advance_to() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+        game.advance_to(100);
+        assert_eq!(game.tick_counter, 100);
+
+        game.advance_to(50);
+        assert_eq!(game.tick_counter, 100);
+    }
+
+    #[test]
+    fn test_buzzer_beater() {
+        let mut level = Level::tutorial(6);
+        level.gravity = Vec2::ZERO;
+        level.input.motor.active = false;
+        level.body.angular_velocity = 0.0;
+        level.balls[0].center = Pos2::new(0.6, 0.35);
+        level.balls[0].velocity = 2.0 * Vec2::angled(std::f32::consts::FRAC_PI_6);
+
+        let mut probe = Game::new(level.clone(), 1024.);
+        while !probe.has_escaped() {
+            probe.tick();
+        }
+
+        // The time limit runs out on exactly the tick the ball escapes, and the frame comes late
+        level.max_time = web_time::Duration::from_secs_f32(probe.tick_counter as f32 / 1024.);
+        let mut game = Game::new(level, 1024.);
+        game.start_time -= web_time::Duration::from_secs(1);
+
+        assert!(matches!(game.update(), State::Victory));
+        assert_eq!(game.tick_counter, probe.tick_counter);
+    }
+
+    #[test]
+    fn test_escape_progress() {
+        let mut level = Level::tutorial(6);
+        level.balls[0].center = Pos2::ZERO;
+        let mut game = Game::new(level, 1024.);
+        assert!(game.escape_progress() < 0.3);
+
+        let (gap_start, gap_end) = game.level.body.shape.openings()[0];
+        let gap_middle = gap_start.lerp(gap_end, 0.5);
+        let radius = game.level.balls[0].radius;
+        game.level.balls[0].center = gap_middle - radius * gap_middle.to_vec2().normalized();
+        assert!(game.escape_progress() > 0.99);
+
+        assert_eq!(
+            Game::new(Level::simple_polygon(6), 1024.).escape_progress(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_trail() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+        game.advance_to(1024);
+        assert_eq!(game.level.balls[0].trail.len(), crate::ball::TRAIL_LENGTH);
+
+        game.respawn_ball(0);
+        assert!(game.level.balls[0].trail.is_empty());
+    }
+
+    #[test]
+    fn test_gravity_flip_event_fires_once() {
+        let mut level = Level::simple_polygon(6);
+        level.events.push(LevelEvent {
+            trigger_time_secs: 0.5,
+            action: LevelAction::SetGravity(Vec2::new(0.0, -9.81)),
+        });
+
+        let mut game = Game::new(level, 1024.);
+        let trigger_tick = 512;
+
+        while game.tick_counter < trigger_tick - 1 {
+            game.tick_counter += 1;
+            game.fire_events();
+        }
+        assert_eq!(game.events_fired, 0);
+        assert_eq!(game.level.gravity, Vec2::new(0.0, 9.81));
+
+        game.tick_counter += 1;
+        game.fire_events();
+        assert_eq!(game.events_fired, 1);
+        assert_eq!(game.level.gravity, Vec2::new(0.0, -9.81));
+
+        // Overwrite gravity to confirm the event isn't applied a second time
+        game.level.gravity = Vec2::ZERO;
+        for _ in 0..1024 {
+            game.tick_counter += 1;
+            game.fire_events();
+        }
+        assert_eq!(game.events_fired, 1);
+        assert_eq!(game.level.gravity, Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_check_finite() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+        assert_eq!(game.check_finite(), Ok(()));
+
+        game.level.balls[0].velocity.y = f32::NAN;
+        assert_eq!(
+            game.check_finite(),
+            Err(GameError::PhysicsNaN {
+                field: "ball.velocity"
+            })
+        );
+    }
+
+    #[test]
+    fn test_draw_to_svg() {
+        let game = Game::new(Level::simple_polygon(6), 1024.);
+        let svg = game.draw_to_svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("viewBox=\"-1.2 -1.2 2.4 2.4\""));
+        assert_eq!(
+            svg.matches("<path ").count(),
+            game.level.body.shape.lines.len()
+        );
+        assert_eq!(svg.matches("<circle ").count(), 1);
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_checkpoint_split_times() {
+        let mut level = Level::simple_polygon(6);
+        level.gravity = Vec2::ZERO;
+        level.balls[0].velocity = Vec2::new(0.5, 0.0);
+        level.body.angular_velocity = 0.0;
+        level.input.motor.active = false;
+        level.checkpoints = vec![
+            Checkpoint::new(Pos2::new(0.25, 0.0), 0.05, "Near"),
+            Checkpoint::new(Pos2::new(0.0, 0.5), 0.05, "Unreached"),
+        ];
+
+        let mut game = Game::new(level, 1024.).with_history(2048);
+        assert_eq!(game.checkpoint_times, vec![None, None]);
+
+        // The ball covers 0.2 units in 0.4 s and the checkpoint's edge is at 0.2
+        game.advance_to(512);
+        let split = game.checkpoint_times[0].expect("Checkpoint not reached");
+        assert!((split - 0.4).abs() < 0.01, "split was {split}");
+        assert_eq!(game.checkpoint_times[1], None);
+        assert_eq!(
+            game.split_times().collect::<Vec<_>>(),
+            vec![("Near", split)]
+        );
+
+        // The time stays at the first visit
+        game.advance_to(600);
+        assert_eq!(game.checkpoint_times[0], Some(split));
+
+        // Rolling back to before the checkpoint was reached clears its time
+        game.rollback(300).unwrap();
+        assert_eq!(game.checkpoint_times[0], None);
+    }
+
+    #[test]
+    fn test_work_history_sampled_every_second() {
+        let mut game = Game::new(Level::simple_polygon(6), 1024.).with_history(4096);
+        game.level.input.boost.active = true;
+
+        game.advance_to(3 * 1024 + 100);
+        let times: Vec<f32> = game.work_history.iter().map(|(time, _)| *time).collect();
+        assert_eq!(times, vec![0.0, 1.0, 2.0, 3.0]);
+
+        let work: Vec<f32> = game.work_history.iter().map(|(_, work)| *work).collect();
+        assert!(work.windows(2).all(|pair| pair[1] > pair[0]));
+        assert!(work[3] <= game.input_work.brake + game.input_work.boost);
+
+        game.rollback(1024).unwrap();
+        assert_eq!(game.work_history.len(), 3);
+    }
+
+    #[test]
+    fn test_ball_initial_velocity() {
+        let mut level = Level::simple_polygon(6);
+        level.ball_initial_velocity = Vec2::new(1.0, 0.0);
+
+        let mut game = Game::new(level, 1024.);
+        assert_eq!(game.level.balls[0].velocity, Vec2::new(1.0, 0.0));
+
+        // Respawning relaunches the ball
+        game.tick();
+        game.respawn_ball(0);
+        assert_eq!(game.level.balls[0].velocity, Vec2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_trail_matches_ball_color() {
+        let mut level = Level::simple_polygon(6);
+        level.ball_color = Some(Color32::BLUE);
+        let mut game = Game::new(level, 1024.);
+        for _ in 0..64 {
+            game.tick();
+        }
+        assert!(!game.level.balls[0].trail.is_empty());
+
+        let ctx = egui::Context::default();
+        let output = ctx.run(egui::RawInput::default(), |ctx| {
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            game.draw_trails(&painter, TSTransform::from_scaling(100.0));
+        });
+        let fills: Vec<Color32> = output
+            .shapes
+            .iter()
+            .filter_map(|clipped| match &clipped.shape {
+                egui::Shape::Circle(circle) => Some(circle.fill),
+                _ => None,
+            })
+            .collect();
+        assert!(!fills.is_empty());
+        assert!(fills.iter().all(|fill| fill.r() == 0 && fill.g() == 0));
+    }
+
+    #[test]
+    fn test_ball_color_from_level() {
+        let mut level = Level::simple_polygon(6);
+        level.ball_color = Some(Color32::BLUE);
+        level.balls.push(Ball {
+            color: Some(Color32::GREEN),
+            ..Default::default()
+        });
+
+        let game = Game::new(level, 1024.);
+        assert_eq!(game.level.balls[0].color, Some(Color32::BLUE));
+        assert_eq!(game.level.balls[1].color, Some(Color32::GREEN));
+        assert!(game.draw_to_svg().contains("fill=\"#0000ff\""));
     }
 }
@@ -1,35 +1,256 @@
-use egui::Pos2;
-use serde::{Deserialize, Serialize};
+use egui::{Color32, Pos2, Vec2};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     ball::Ball,
+    checkpoint::Checkpoint,
     control::{Input, InputSet},
+    escape::EscapeZone,
+    field::{ForceField, GravityWell},
+    platform::MovingPlatform,
     rotating::Body,
     shape::Shape,
+    tutorial::{TutorialStep, TutorialTrigger},
 };
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum LevelAction {
+    SetGravity(Vec2),
+    SetMaxBrakeWork(f32),
+    SetMaxBoostWork(f32),
+    SetAngularVelocityLimit((f32, f32)),
+    AddForceField(ForceField),
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct LevelEvent {
+    pub trigger_time_secs: f32,
+    pub action: LevelAction,
+}
+
+pub const CURRENT_VERSION: u32 = 4;
+
+fn default_version() -> u32 {
+    1
+}
+
+fn default_rewind_cost() -> f32 {
+    10.0
+}
+
+// Deserialization goes through `migrate` first, so the derived impls are generated as inherent
+// functions and wrapped by the trait impls below
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(remote = "Self")]
 pub struct Level {
+    #[serde(default = "default_version")]
+    pub version: u32,
     pub id: uuid::Uuid,
+    pub id_name: String,
     pub name: String,
     pub body: Body,
-    pub ball: Ball,
+    pub balls: Vec<Ball>,
     pub input: InputSet,
-    pub gravity: f32,
+    pub gravity: Vec2,
+    /// Serialized in serde's `Duration` format, `{ "secs": u64, "nanos": u32 }`
     pub max_time: web_time::Duration,
-    pub max_work: f32,
+    pub max_brake_work: f32,
+    pub max_boost_work: f32,
+    #[serde(default)]
+    pub moving_platform: Vec<MovingPlatform>,
+    #[serde(default)]
+    pub escape_zones: Vec<EscapeZone>,
+    #[serde(default)]
+    pub events: Vec<LevelEvent>,
+    #[serde(default)]
+    pub initial_body_angle: f32,
+    #[serde(default)]
+    pub camera_follow: bool,
+    #[serde(default)]
+    pub tutorial_steps: Vec<TutorialStep>,
+    #[serde(default)]
+    pub gravity_wells: Vec<GravityWell>,
+    #[serde(default)]
+    pub force_fields: Vec<ForceField>,
+    #[serde(default)]
+    pub background_color: Option<Color32>,
+    #[serde(default)]
+    pub is_practice: bool,
+    #[serde(default = "default_rewind_cost")]
+    pub rewind_cost: f32,
+    #[serde(default)]
+    pub checkpoints: Vec<Checkpoint>,
+    /// Launch velocity given to every ball at the start, on top of its own velocity
+    #[serde(default)]
+    pub ball_initial_velocity: Vec2,
+    /// Colour of balls that don't set their own, or the theme's error colour if unset
+    #[serde(default)]
+    pub ball_color: Option<Color32>,
+}
+
+impl Serialize for Level {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Level::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Level {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = serde_json::Value::deserialize(deserializer)?;
+        let json = migrate(json).map_err(serde::de::Error::custom)?;
+        Level::deserialize(json).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A named pack of levels, shipped together as one JSON file
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct LevelBundle {
+    pub name: String,
+    pub description: String,
+    /// Version of the pack itself, separate from each level's format version
+    pub version: u32,
+    pub levels: Vec<Level>,
+}
+
+impl LevelBundle {
+    /// The levels shipped with the game, starting with the tutorial
+    pub fn builtin() -> Self {
+        let mut bundle = Self::load_from_json(include_str!("../data/default_levels.json"))
+            .expect("Failed to parse the built-in levels");
+        bundle.levels.insert(0, Level::tutorial(6));
+        bundle
+    }
+
+    /// A bundle holding a single level, named after it
+    pub fn from_level(level: Level) -> Self {
+        Self {
+            name: level.name.clone(),
+            description: String::new(),
+            version: 1,
+            levels: vec![level],
+        }
+    }
+
+    pub fn load_from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    pub fn save_to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("levels always serialize to JSON")
+    }
+}
+
+/// Upgrades serialized level JSON from any older version to `CURRENT_VERSION`. Fields that were
+/// added with a usable default rely on `#[serde(default)]` instead of a patch here. Fails on a
+/// version with no migration path, such as one from a newer release of the game.
+pub fn migrate(mut json: serde_json::Value) -> Result<serde_json::Value, String> {
+    let Some(object) = json.as_object_mut() else {
+        return Ok(json);
+    };
+
+    let version = object
+        .get("version")
+        .and_then(|version| version.as_u64())
+        .map_or(default_version(), |version| version as u32);
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "Level version {version} is newer than the latest supported version {CURRENT_VERSION}"
+        ));
+    }
+
+    for from_version in version..CURRENT_VERSION {
+        match from_version {
+            1 => migrate_v1_to_v2(object),
+            2 => migrate_v2_to_v3(object),
+            3 => migrate_v3_to_v4(object),
+            _ => return Err(format!("No migration from level version {from_version}")),
+        }
+    }
+
+    if version < CURRENT_VERSION {
+        object.insert("version".to_owned(), CURRENT_VERSION.into());
+    }
+
+    Ok(json)
+}
+
+fn migrate_v1_to_v2(object: &mut serde_json::Map<String, serde_json::Value>) {
+    // Gravity changed from a downward magnitude to a vector
+    if let Some(gravity) = object.get("gravity").and_then(|gravity| gravity.as_f64()) {
+        object.insert(
+            "gravity".to_owned(),
+            serde_json::json!({ "x": 0.0, "y": gravity }),
+        );
+    }
+
+    if !object.contains_key("id_name") {
+        let name = object
+            .get("name")
+            .and_then(|name| name.as_str())
+            .unwrap_or_default();
+        object.insert("id_name".to_owned(), id_name_from(name).into());
+    }
+}
+
+fn migrate_v2_to_v3(object: &mut serde_json::Map<String, serde_json::Value>) {
+    // Levels went from a single ball to a list of balls
+    if let Some(ball) = object.remove("ball") {
+        object.insert("balls".to_owned(), serde_json::Value::Array(vec![ball]));
+    }
+}
+
+fn migrate_v3_to_v4(object: &mut serde_json::Map<String, serde_json::Value>) {
+    // The shared work budget was split into one for each input, each as large as the old one
+    if let Some(max_work) = object.remove("max_work") {
+        object.insert("max_brake_work".to_owned(), max_work.clone());
+        object.insert("max_boost_work".to_owned(), max_work);
+    }
+
+    let Some(events) = object
+        .get_mut("events")
+        .and_then(|events| events.as_array_mut())
+    else {
+        return;
+    };
+
+    *events = std::mem::take(events)
+        .into_iter()
+        .flat_map(|mut event| {
+            let max_work = event
+                .pointer_mut("/action")
+                .and_then(|action| action.as_object_mut())
+                .and_then(|action| action.remove("SetMaxWork"));
+            let Some(max_work) = max_work else {
+                return vec![event];
+            };
+
+            let mut boost_event = event.clone();
+            event["action"] = serde_json::json!({ "SetMaxBrakeWork": max_work });
+            boost_event["action"] = serde_json::json!({ "SetMaxBoostWork": max_work });
+            vec![event, boost_event]
+        })
+        .collect();
+}
+
+pub fn id_name_from(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
 }
 
 impl Level {
     pub fn simple_polygon(num_sides: usize) -> Self {
         let id = uuid::Uuid::new_v4();
         let name = format!("Simple Polygon {}", num_sides);
+        let id_name = id_name_from(&name);
         let shape = Shape::regular_polygon(num_sides, 1.0, Pos2::ZERO);
         let body = Body {
             shape,
             ..Default::default()
         };
-        let ball = Ball::default();
+        let balls = vec![Ball::default()];
         let input = InputSet {
             brake: Input {
                 torque: 3.0,
@@ -44,19 +265,240 @@ impl Level {
                 active: false,
             },
         };
-        let gravity = 9.81;
+        let gravity = Vec2::new(0.0, 9.81);
         let max_time = web_time::Duration::from_secs(45);
-        let max_work = 50.0;
+        let max_brake_work = 50.0;
+        let max_boost_work = 50.0;
 
         Self {
+            version: CURRENT_VERSION,
             id,
+            id_name,
             name,
             body,
-            ball,
+            balls,
             input,
             gravity,
             max_time,
-            max_work,
+            max_brake_work,
+            max_boost_work,
+            moving_platform: Vec::new(),
+            escape_zones: Vec::new(),
+            events: Vec::new(),
+            initial_body_angle: 0.0,
+            camera_follow: false,
+            tutorial_steps: Vec::new(),
+            gravity_wells: Vec::new(),
+            force_fields: Vec::new(),
+            background_color: None,
+            is_practice: false,
+            rewind_cost: default_rewind_cost(),
+            checkpoints: Vec::new(),
+            ball_initial_velocity: Vec2::ZERO,
+            ball_color: None,
+        }
+    }
+
+    pub fn tutorial(num_sides: usize) -> Self {
+        let mut level = Self::simple_polygon(num_sides);
+        level.name = format!("Tutorial {}", num_sides);
+        level.id_name = id_name_from(&level.name);
+        level.body.shape = level.body.shape.cut_gap(0, 0, 0.2);
+
+        let steps = [
+            (
+                TutorialTrigger::OnGameStart,
+                "The shape spins on its own. Get the ball out through the gap!",
+            ),
+            (
+                TutorialTrigger::OnFirstCollision,
+                "Hold Brake to slow the spin and line the gap up with the ball.",
+            ),
+            (
+                TutorialTrigger::OnFirstBrakePress,
+                "Braking uses power. Hold Boost to spin faster instead.",
+            ),
+            (
+                TutorialTrigger::OnFirstBoostPress,
+                "Boosting uses power too. Escape before the power or time runs out!",
+            ),
+        ];
+        level.tutorial_steps = steps
+            .into_iter()
+            .map(|(trigger, message)| TutorialStep {
+                trigger,
+                message: message.to_owned(),
+            })
+            .collect();
+
+        level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1_LEVEL: &str = r#"{
+        "id": "d7a97ce7-bd14-4a0f-88c4-5646fa0ed646",
+        "name": "Old Square",
+        "body": {
+            "shape": {
+                "lines": [[
+                    { "x": 1.0, "y": 0.0 },
+                    { "x": 0.0, "y": 1.0 },
+                    { "x": -1.0, "y": 0.0 },
+                    { "x": 0.0, "y": -1.0 }
+                ]]
+            },
+            "center_of_rotation": { "x": 0.0, "y": 0.0 },
+            "angle": 0.0,
+            "angular_velocity": 1.0,
+            "moment_of_inertia": 1.0,
+            "friction_coefficient": 0.7
+        },
+        "ball": {
+            "center": { "x": 0.0, "y": 0.0 },
+            "radius": 0.05,
+            "velocity": { "x": 0.0, "y": 0.0 }
+        },
+        "input": {
+            "brake": { "torque": 3.0, "active": false },
+            "motor": { "torque": 1.0, "active": true },
+            "boost": { "torque": 2.0, "active": false }
+        },
+        "gravity": 9.81,
+        "max_time": { "secs": 45, "nanos": 0 },
+        "max_work": 50.0
+    }"#;
+
+    #[test]
+    fn test_migrate_v1_level() {
+        let level: Level = serde_json::from_str(V1_LEVEL).unwrap();
+
+        assert_eq!(level.version, CURRENT_VERSION);
+        assert_eq!(level.id_name, "old-square");
+        assert_eq!(level.gravity, Vec2::new(0.0, 9.81));
+        assert_eq!(level.balls.len(), 1);
+        assert_eq!(level.balls[0].radius, 0.05);
+        assert_eq!(level.body.angular_velocity_limit, None);
+        assert!(level.moving_platform.is_empty());
+        assert!(level.escape_zones.is_empty());
+        assert!(level.events.is_empty());
+        assert_eq!(level.initial_body_angle, 0.0);
+        assert_eq!(level.max_brake_work, 50.0);
+        assert_eq!(level.max_boost_work, 50.0);
+    }
+
+    #[test]
+    fn test_unsupported_version_fails() {
+        for version in [0, 99] {
+            let mut json: serde_json::Value = serde_json::from_str(V1_LEVEL).unwrap();
+            json["version"] = version.into();
+            assert!(serde_json::from_value::<Level>(json).is_err());
+        }
+
+        let json = V1_LEVEL.replace("\"name\"", "\"version\": 99, \"name\"");
+        assert!(serde_json::from_str::<Level>(&json).is_err());
+    }
+
+    #[test]
+    fn test_migrate_v3_work_budget_events() {
+        let mut json: serde_json::Value = serde_json::from_str(V1_LEVEL).unwrap();
+        migrate_v3_to_v4(json.as_object_mut().unwrap());
+        assert!(json.get("max_work").is_none());
+
+        json["events"] = serde_json::json!([
+            { "trigger_time_secs": 1.0, "action": { "SetMaxWork": 20.0 } },
+            { "trigger_time_secs": 2.0, "action": { "SetGravity": { "x": 0.0, "y": 1.0 } } }
+        ]);
+        migrate_v3_to_v4(json.as_object_mut().unwrap());
+
+        let events: Vec<LevelEvent> = serde_json::from_value(json["events"].clone()).unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0].action, LevelAction::SetMaxBrakeWork(work) if work == 20.0));
+        assert!(matches!(events[1].action, LevelAction::SetMaxBoostWork(work) if work == 20.0));
+        assert!(matches!(events[2].action, LevelAction::SetGravity(_)));
+    }
+
+    #[test]
+    fn test_current_version_round_trip() {
+        let level = Level::simple_polygon(5);
+        let json = serde_json::to_string(&level).unwrap();
+        let deserialized: Level = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(deserialized.version, CURRENT_VERSION);
+        assert_eq!(deserialized.id_name, level.id_name);
+        assert_eq!(deserialized.gravity, level.gravity);
+    }
+
+    #[test]
+    fn test_default_levels_deserialize() {
+        let bundle = LevelBundle::builtin();
+
+        assert_eq!(bundle.name, "SpinScape");
+        assert!(bundle.levels.len() > 1);
+        assert_eq!(bundle.levels[0].id_name, Level::tutorial(6).id_name);
+        assert!(bundle
+            .levels
+            .iter()
+            .all(|level| level.version == CURRENT_VERSION));
+    }
+
+    #[test]
+    fn test_id_name_is_stable() {
+        let a = Level::simple_polygon(6);
+        let b = Level::simple_polygon(6);
+
+        assert_ne!(a.id, b.id);
+        assert_eq!(a.id_name, b.id_name);
+        assert_eq!(a.id_name, "simple-polygon-6");
+    }
+
+    #[test]
+    fn test_id_name_from() {
+        assert_eq!(id_name_from("Funky Polygon"), "funky-polygon");
+        assert_eq!(id_name_from("  Level #2: Spin! "), "level-2-spin");
+    }
+
+    fn round_trip(level: &Level) -> Level {
+        let json = serde_json::to_string(level).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_built_in_levels_round_trip() {
+        let levels = (3..=8)
+            .map(Level::simple_polygon)
+            .chain(LevelBundle::builtin().levels);
+
+        for level in levels {
+            let restored = round_trip(&level);
+            assert_eq!(restored.id, level.id);
+            assert_eq!(restored.body.shape.lines, level.body.shape.lines);
+            assert_eq!(restored, level, "{} changed in a round trip", level.name);
         }
     }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let mut bundle = LevelBundle::builtin();
+        bundle.levels.push(Level::simple_polygon(5));
+
+        let restored = LevelBundle::load_from_json(&bundle.save_to_json()).unwrap();
+        assert_eq!(restored, bundle);
+    }
+
+    #[test]
+    fn test_max_time_format() {
+        let mut level = Level::simple_polygon(6);
+        level.max_time = web_time::Duration::from_millis(45_250);
+
+        let json = serde_json::to_value(&level).unwrap();
+        assert_eq!(
+            json["max_time"],
+            serde_json::json!({ "secs": 45, "nanos": 250_000_000 })
+        );
+        assert_eq!(round_trip(&level).max_time, level.max_time);
+    }
 }
@@ -3,11 +3,24 @@
 mod app;
 pub use app::App;
 
+pub mod achievement;
 pub mod ball;
+pub mod checkpoint;
 pub mod collision;
 pub mod control;
 pub mod drawable;
+pub mod error;
+pub mod escape;
+pub mod field;
 pub mod game;
 pub mod level;
+pub mod metrics;
+pub mod platform;
+pub mod rng;
 pub mod rotating;
+pub mod run_history;
+pub mod settings;
 pub mod shape;
+pub mod tutorial;
+pub mod ui;
+pub mod util;
@@ -6,9 +6,7 @@
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
-    let levels_data = include_str!("../data/default_levels.json");
-    let levels: Vec<bouncy::level::Level> =
-        serde_json::from_str(levels_data).expect("Failed to parse levels");
+    let bundle = bouncy::level::LevelBundle::builtin();
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -28,7 +26,7 @@ fn main() -> eframe::Result {
             cc.egui_ctx.options_mut(|options| {
                 options.input_options.max_click_duration = f64::INFINITY;
             });
-            Ok(Box::new(bouncy::App::new(cc, 1024.0, levels)))
+            Ok(Box::new(bouncy::App::new(cc, bundle)))
         }),
     )
 }
@@ -41,9 +39,7 @@ fn main() {
     // Redirect `log` message to `console.log` and friends:
     eframe::WebLogger::init(log::LevelFilter::Debug).ok();
 
-    let levels_data = include_str!("../data/default_levels.json");
-    let levels: Vec<bouncy::level::Level> =
-        serde_json::from_str(levels_data).expect("Failed to parse levels");
+    let bundle = bouncy::level::LevelBundle::builtin();
 
     let web_options = eframe::WebOptions::default();
 
@@ -67,7 +63,7 @@ fn main() {
                     cc.egui_ctx.options_mut(|options| {
                         options.input_options.max_click_duration = f64::INFINITY;
                     });
-                    Ok(Box::new(bouncy::App::new(cc, 1024.0, levels)))
+                    Ok(Box::new(bouncy::App::new(cc, bundle)))
                 }),
             )
             .await;
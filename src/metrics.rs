@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{self, Game};
+
+/// Performance counters over every session played, for checking that the physics keeps up
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppMetrics {
+    pub total_physics_ticks: u64,
+    /// Frames rendered while a level was being played. Menus only repaint on input, so their
+    /// frames would drag the average down.
+    pub total_render_frames: u64,
+    /// Wall time covered by `total_render_frames`
+    pub playing_secs: f64,
+    pub average_measured_fps: f32,
+    /// Frames on which the physics had fallen more than a second behind the clock
+    pub tick_overrun_count: u32,
+    pub collision_events_lifetime: u64,
+    pub sessions: u32,
+}
+
+impl AppMetrics {
+    /// Counts a frame of play that took `frame_secs` since the one before it
+    pub fn track_frame(&mut self, frame_secs: f64) {
+        self.total_render_frames += 1;
+        self.playing_secs += frame_secs;
+        self.average_measured_fps = (self.total_render_frames as f64 / self.playing_secs) as f32;
+    }
+
+    /// Updates the game, counting the ticks, collisions and overruns that happened on the way
+    pub fn track_update(&mut self, game: &mut Game) -> game::State {
+        let ticks = game.tick_counter;
+        let collisions = game.stats.total_collisions;
+        let overruns = game.tick_overruns;
+
+        let state = game.update();
+
+        self.total_physics_ticks += game.tick_counter.saturating_sub(ticks);
+        self.collision_events_lifetime +=
+            game.stats.total_collisions.saturating_sub(collisions) as u64;
+        self.tick_overrun_count += game.tick_overruns.saturating_sub(overruns);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+
+    #[test]
+    fn test_track_update_counts_overruns() {
+        let mut metrics = AppMetrics::default();
+        let mut game = Game::new(Level::simple_polygon(6), 1024.);
+
+        // Starting two seconds ago leaves the physics well behind on the first update
+        game.start_time -= web_time::Duration::from_secs(2);
+        metrics.track_update(&mut game);
+
+        assert_eq!(metrics.tick_overrun_count, 1);
+        assert_eq!(metrics.total_physics_ticks, game.tick_counter);
+        assert!(metrics.total_physics_ticks >= 2048);
+        assert_eq!(
+            metrics.collision_events_lifetime,
+            game.stats.total_collisions as u64
+        );
+
+        // Caught up, so the next update isn't an overrun
+        metrics.track_update(&mut game);
+        assert_eq!(metrics.tick_overrun_count, 1);
+    }
+
+    #[test]
+    fn test_track_frame() {
+        let mut metrics = AppMetrics::default();
+        for _ in 0..30 {
+            metrics.track_frame(1.0 / 60.0);
+        }
+        metrics.track_frame(0.5);
+
+        assert_eq!(metrics.total_render_frames, 31);
+        assert!((metrics.playing_secs - 1.0).abs() < 1e-9);
+        assert!((metrics.average_measured_fps - 31.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,94 @@
+use egui::{emath::TSTransform, Pos2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{drawable::Drawable, shape::Shape};
+
+/// An obstacle that translates along a path of waypoints, looping back to the
+/// first waypoint after the last one. The shape is defined relative to the
+/// platform's current position.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MovingPlatform {
+    pub shape: Shape,
+    pub waypoints: Vec<Pos2>,
+    pub speed: f32,
+    #[serde(default)]
+    pub progress: f32,
+    #[serde(default)]
+    pub current_waypoint: usize,
+}
+
+impl MovingPlatform {
+    fn segment_endpoints(&self) -> (Pos2, Pos2) {
+        match self.waypoints.len() {
+            0 => (Pos2::ZERO, Pos2::ZERO),
+            1 => (self.waypoints[0], self.waypoints[0]),
+            n => {
+                let i = self.current_waypoint % n;
+                (self.waypoints[i], self.waypoints[(i + 1) % n])
+            }
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.waypoints.len() < 2 {
+            return;
+        }
+
+        self.progress += self.speed * dt;
+        while self.progress >= 1.0 {
+            self.progress -= 1.0;
+            self.current_waypoint = (self.current_waypoint + 1) % self.waypoints.len();
+        }
+    }
+
+    pub fn position(&self) -> Pos2 {
+        let (start, end) = self.segment_endpoints();
+        start.lerp(end, self.progress)
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        let (start, end) = self.segment_endpoints();
+        (end - start) * self.speed
+    }
+
+    pub fn shape_at_position(&self) -> Shape {
+        self.shape.translate(self.position().to_vec2())
+    }
+}
+
+impl Drawable for MovingPlatform {
+    fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
+        self.shape_at_position().draw(ctx, painter, transform);
+    }
+
+    fn draw_to_svg(&self) -> String {
+        self.shape_at_position().draw_to_svg()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_moves_between_waypoints() {
+        let mut platform = MovingPlatform {
+            shape: Shape::regular_polygon(4, 0.1, Pos2::ZERO),
+            waypoints: vec![Pos2::new(-0.5, 0.0), Pos2::new(0.5, 0.0)],
+            speed: 0.5,
+            progress: 0.0,
+            current_waypoint: 0,
+        };
+
+        assert_eq!(platform.position(), Pos2::new(-0.5, 0.0));
+        assert_eq!(platform.velocity(), Vec2::new(0.5, 0.0));
+
+        // Advance by a total of 1 / speed seconds
+        for _ in 0..8 {
+            platform.update(0.25);
+        }
+
+        assert_eq!(platform.position(), Pos2::new(0.5, 0.0));
+        assert_eq!(platform.current_waypoint, 1);
+    }
+}
@@ -0,0 +1,36 @@
+/// Small, dependency-free xorshift generator for things that only need to look random
+#[derive(Clone, Debug)]
+pub struct XorShift(u64);
+
+impl XorShift {
+    /// A zero state would only ever produce zeros, so it is bumped to one
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// Uniform in `[0, 1)`
+    pub fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform in `[min, max)`
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_seed_still_varies() {
+        let mut rng = XorShift::new(0);
+        let first = rng.next_f32();
+        assert!((0.0..1.0).contains(&first));
+        assert_ne!(first, rng.next_f32());
+    }
+}
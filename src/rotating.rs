@@ -13,7 +13,7 @@ pub struct Collision {
     pub collision: collision::Collision,
     pub center_of_rotation: Pos2,
     pub angle: f32,
-    pub time: web_time::Instant,
+    pub age: f32,
 }
 
 pub type CollisionList = ringbuffer::AllocRingBuffer<Collision>;
@@ -24,21 +24,31 @@ impl Collision {
             collision,
             center_of_rotation,
             angle: 0.0,
-            time: web_time::Instant::now(),
+            age: 0.0,
         }
     }
 
-    pub fn update(&mut self, delta_angle: f32) {
+    pub fn update(&mut self, delta_angle: f32, dt: f32) {
         self.angle += delta_angle;
+        self.age += dt;
+    }
+
+    /// Harder hits make bigger sparkles
+    pub fn size(&self) -> f32 {
+        self.collision.velocity_at_impact.sqrt() * self.age * 8.0
+    }
+
+    /// Harder hits also last longer
+    pub fn max_age(&self) -> f32 {
+        0.5 + self.collision.velocity_at_impact * 0.1
     }
 }
 
 impl Drawable for Collision {
     fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
         let collision = self.collision.rotate(self.angle, self.center_of_rotation);
-        let age = (web_time::Instant::now() - self.time).as_secs_f32();
-        let size = 10. * age;
-        let opacity = 1.0 - age / 2.;
+        let size = self.size();
+        let opacity = 1.0 - self.age / self.max_age();
 
         if size <= 0.0 || opacity <= 0.0 {
             return;
@@ -63,7 +73,7 @@ pub struct BodyUpdateResult {
     pub delta_angle: f32,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Body {
     pub shape: Shape,
     pub center_of_rotation: egui::Pos2,
@@ -71,6 +81,10 @@ pub struct Body {
     pub angular_velocity: f32,
     pub moment_of_inertia: f32,
     pub friction_coefficient: f32,
+    #[serde(default)]
+    pub angular_velocity_limit: Option<(f32, f32)>,
+    #[serde(default)]
+    pub color: Option<Color32>,
 }
 
 impl Default for Body {
@@ -82,6 +96,8 @@ impl Default for Body {
             angular_velocity: 1.0,
             moment_of_inertia: 1.0,
             friction_coefficient: 0.7,
+            angular_velocity_limit: None,
+            color: None,
         }
     }
 }
@@ -120,6 +136,15 @@ impl Body {
             self.angular_velocity += angular_acceleration * dt;
         }
 
+        if let Some((mut min, mut max)) = self.angular_velocity_limit {
+            // The limits come from level files, so tolerate them being reversed or NaN, either of
+            // which would make `clamp` panic. `f32::max` and `f32::min` ignore a NaN bound.
+            if min > max {
+                std::mem::swap(&mut min, &mut max);
+            }
+            self.angular_velocity = self.angular_velocity.max(min).min(max);
+        }
+
         let delta_angle = self.angular_velocity * dt;
         self.angle += delta_angle;
 
@@ -141,6 +166,140 @@ impl Body {
 impl Drawable for Body {
     fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
         let shape = self.shape.rotate(self.angle, self.center_of_rotation);
-        shape.draw(ctx, painter, transform);
+        match self.color {
+            Some(color) => shape.draw_with_color(painter, transform, color),
+            None => shape.draw(ctx, painter, transform),
+        }
+    }
+
+    fn draw_to_svg(&self) -> String {
+        self.shape_with_rotation_applied().draw_to_svg()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkle_scales_with_impact() {
+        let sparkle = |velocity_at_impact: f32| {
+            let collision = collision::Collision::new(Pos2::ZERO, egui::Vec2::X)
+                .with_velocity_at_impact(velocity_at_impact);
+            let mut sparkle = Collision::new(collision, Pos2::ZERO);
+            sparkle.update(0.0, 0.1);
+            sparkle
+        };
+
+        let (soft, hard) = (sparkle(1.0), sparkle(4.0));
+        assert!((hard.size() - 2.0 * soft.size()).abs() < 1e-5);
+        assert!(hard.max_age() > soft.max_age());
+    }
+
+    const DT: f32 = 1e-3;
+
+    /// Inputs with the given torques, all inactive
+    fn inputs(brake: f32, motor: f32, boost: f32) -> InputSet {
+        let input = |torque| crate::control::Input {
+            torque,
+            active: false,
+        };
+        InputSet {
+            brake: input(brake),
+            motor: input(motor),
+            boost: input(boost),
+        }
+    }
+
+    fn frictionless_body(angular_velocity: f32) -> Body {
+        Body {
+            angular_velocity,
+            moment_of_inertia: 2.0,
+            friction_coefficient: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_motor_accelerates_body() {
+        let mut body = frictionless_body(1.0);
+        let mut input = inputs(0.0, 0.5, 0.0);
+        input.motor.active = true;
+
+        body.update(input, DT);
+        assert!((body.angular_velocity - (1.0 + 0.5 / 2.0 * DT)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_friction_decays_spin() {
+        let mut body = Body {
+            friction_coefficient: 0.7,
+            ..frictionless_body(-2.0)
+        };
+
+        let mut previous = body.angular_velocity;
+        for _ in 0..100 {
+            body.update(inputs(0.0, 0.0, 0.0), DT);
+            assert!(body.angular_velocity < 0.0);
+            assert!(body.angular_velocity.abs() < previous.abs());
+            previous = body.angular_velocity;
+        }
+    }
+
+    #[test]
+    fn test_brake_stops_slow_body() {
+        let mut body = frictionless_body(0.0005);
+        let mut input = inputs(3.0, 0.0, 0.0);
+        input.brake.active = true;
+
+        let result = body.update(input, DT);
+        assert_eq!(body.angular_velocity, 0.0);
+        assert_eq!(result.delta_angle, 0.0);
+    }
+
+    #[test]
+    fn test_boost_work() {
+        let mut body = frictionless_body(1.0);
+        let mut input = inputs(0.0, 0.0, 2.0);
+        input.boost.active = true;
+
+        let result = body.update(input, DT);
+        assert!(result.delta_angle > 0.0);
+        assert!((result.work.boost - 2.0 * result.delta_angle).abs() < 1e-9);
+        assert_eq!(result.work.brake, 0.0);
+    }
+
+    #[test]
+    fn test_no_torque_keeps_spin() {
+        let mut body = frictionless_body(1.5);
+
+        let result = body.update(inputs(3.0, 1.0, 2.0), DT);
+        assert_eq!(body.angular_velocity, 1.5);
+        assert!((result.delta_angle - 1.5 * DT).abs() < 1e-9);
+        assert!((body.angle - 1.5 * DT).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_angular_velocity_limit() {
+        let mut body = Body {
+            angular_velocity_limit: Some((-1.0, 1.0)),
+            ..frictionless_body(3.0)
+        };
+        body.update(inputs(0.0, 0.0, 0.0), DT);
+        assert_eq!(body.angular_velocity, 1.0);
+
+        // Reversed bounds are treated as the same range
+        body.angular_velocity = -3.0;
+        body.angular_velocity_limit = Some((1.0, -1.0));
+        body.update(inputs(0.0, 0.0, 0.0), DT);
+        assert_eq!(body.angular_velocity, -1.0);
+
+        // A NaN bound leaves that side unlimited
+        body.angular_velocity_limit = Some((f32::NAN, 2.0));
+        body.update(inputs(0.0, 0.0, 0.0), DT);
+        assert_eq!(body.angular_velocity, -1.0);
+        body.angular_velocity = 3.0;
+        body.update(inputs(0.0, 0.0, 0.0), DT);
+        assert_eq!(body.angular_velocity, 2.0);
     }
 }
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+
+/// Number of runs kept for each level
+pub const RUN_HISTORY_LENGTH: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Outcome {
+    Victory,
+    Defeat,
+}
+
+impl Outcome {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Outcome::Victory => "🏆",
+            Outcome::Defeat => "💀",
+        }
+    }
+}
+
+/// Summary of a finished run, kept so players can look back at their recent attempts
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RunRecord {
+    pub level_id_name: String,
+    pub outcome: Outcome,
+    pub elapsed_secs: f32,
+    pub work_spent: f32,
+    pub collisions: u32,
+    pub timestamp_unix: u64,
+}
+
+impl RunRecord {
+    pub fn new(game: &Game, outcome: Outcome, timestamp_unix: u64) -> Self {
+        Self {
+            level_id_name: game.level.id_name.clone(),
+            outcome,
+            elapsed_secs: game.scaled_elapsed.as_secs_f32(),
+            work_spent: game.input_work.brake + game.input_work.boost,
+            collisions: game.stats.total_collisions,
+            timestamp_unix,
+        }
+    }
+}
+
+/// Adds a run to the front of a level's history, dropping the oldest past `RUN_HISTORY_LENGTH`
+pub fn push_run(history: &mut Vec<RunRecord>, record: RunRecord) {
+    history.insert(0, record);
+    history.truncate(RUN_HISTORY_LENGTH);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+
+    #[test]
+    fn test_push_run() {
+        let game = Game::new(Level::simple_polygon(6), 1024.);
+        let mut history = Vec::new();
+
+        push_run(&mut history, RunRecord::new(&game, Outcome::Defeat, 100));
+        push_run(&mut history, RunRecord::new(&game, Outcome::Defeat, 200));
+        push_run(&mut history, RunRecord::new(&game, Outcome::Victory, 300));
+
+        assert_eq!(history.len(), 3);
+        let timestamps: Vec<u64> = history.iter().map(|run| run.timestamp_unix).collect();
+        assert_eq!(timestamps, vec![300, 200, 100]);
+        assert_eq!(history[0].outcome, Outcome::Victory);
+
+        for timestamp in 0..20 {
+            push_run(
+                &mut history,
+                RunRecord::new(&game, Outcome::Defeat, 400 + timestamp),
+            );
+        }
+        assert_eq!(history.len(), RUN_HISTORY_LENGTH);
+        assert_eq!(history[0].timestamp_unix, 419);
+    }
+}
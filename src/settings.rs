@@ -0,0 +1,153 @@
+use egui::Key;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    Brake,
+    Boost,
+    Pause,
+    Restart,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 4] = [
+        KeyAction::Brake,
+        KeyAction::Boost,
+        KeyAction::Pause,
+        KeyAction::Restart,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyAction::Brake => "Brake",
+            KeyAction::Boost => "Boost",
+            KeyAction::Pause => "Pause",
+            KeyAction::Restart => "Restart",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeyBindings {
+    pub brake: Key,
+    pub boost: Key,
+    pub pause: Key,
+    pub restart: Key,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            brake: Key::ArrowLeft,
+            boost: Key::ArrowRight,
+            pause: Key::Escape,
+            restart: Key::R,
+        }
+    }
+}
+
+/// User preferences, persisted between sessions
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub target_fps: f32,
+    pub debug_mode: bool,
+    pub key_bindings: KeyBindings,
+    pub fullscreen: bool,
+    pub show_ball_trail: bool,
+    pub collision_sparkles: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            target_fps: 60.0,
+            debug_mode: false,
+            key_bindings: KeyBindings::default(),
+            fullscreen: false,
+            show_ball_trail: true,
+            collision_sparkles: true,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: KeyAction) -> Key {
+        match action {
+            KeyAction::Brake => self.brake,
+            KeyAction::Boost => self.boost,
+            KeyAction::Pause => self.pause,
+            KeyAction::Restart => self.restart,
+        }
+    }
+
+    pub fn set(&mut self, action: KeyAction, key: Key) {
+        match action {
+            KeyAction::Brake => self.brake = key,
+            KeyAction::Boost => self.boost = key,
+            KeyAction::Pause => self.pause = key,
+            KeyAction::Restart => self.restart = key,
+        }
+    }
+
+    pub fn is_bound(&self, key: Key) -> bool {
+        KeyAction::ALL.iter().any(|action| self.key(*action) == key)
+    }
+
+    /// Pairs of actions that are bound to the same key
+    pub fn conflicts(&self) -> Vec<(KeyAction, KeyAction)> {
+        let mut conflicts = Vec::new();
+        for (i, a) in KeyAction::ALL.iter().enumerate() {
+            for b in &KeyAction::ALL[i + 1..] {
+                if self.key(*a) == self.key(*b) {
+                    conflicts.push((*a, *b));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_key_bindings() {
+        let json = r#"{ "brake": "A", "boost": "D", "pause": "P", "restart": "Backspace" }"#;
+        let bindings: KeyBindings = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            bindings,
+            KeyBindings {
+                brake: Key::A,
+                boost: Key::D,
+                pause: Key::P,
+                restart: Key::Backspace,
+            }
+        );
+        assert!(bindings.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_key_binding_conflicts() {
+        let mut bindings = KeyBindings::default();
+        bindings.set(KeyAction::Restart, Key::ArrowLeft);
+
+        assert_eq!(
+            bindings.conflicts(),
+            vec![(KeyAction::Brake, KeyAction::Restart)]
+        );
+    }
+
+    #[test]
+    fn test_app_settings_fill_in_missing_fields() {
+        let settings: AppSettings =
+            serde_json::from_str(r#"{ "target_fps": 30.0, "fullscreen": true }"#).unwrap();
+
+        assert_eq!(settings.target_fps, 30.0);
+        assert!(settings.fullscreen);
+        assert_eq!(settings.key_bindings, KeyBindings::default());
+        assert!(settings.collision_sparkles);
+    }
+}
@@ -1,4 +1,4 @@
-use egui::{emath::TSTransform, Pos2, Rect, Vec2};
+use egui::{emath::TSTransform, Color32, Pos2, Rect, Vec2};
 use serde::{Deserialize, Serialize};
 
 use crate::drawable::Drawable;
@@ -6,12 +6,19 @@ use crate::drawable::Drawable;
 pub type Segment = (Pos2, Pos2);
 pub type Line = Vec<Pos2>;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// Points closer than this are treated as the same point, e.g. the two ends of a closed line
+pub const CLOSING_EPSILON: f32 = 1e-4;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Shape {
     pub lines: Vec<Line>,
 }
 
 impl Shape {
+    pub fn new(lines: Vec<Line>) -> Self {
+        Self { lines }
+    }
+
     pub fn regular_polygon(num_sides: usize, radius: f32, center: Pos2) -> Self {
         let angle = 2. * std::f32::consts::PI / num_sides as f32;
         let lines = (0..num_sides + 1)
@@ -21,7 +28,63 @@ impl Shape {
             })
             .collect();
 
-        Self { lines: vec![lines] }
+        Self::new(vec![lines])
+    }
+
+    /// Star with `num_points` tips, alternating between the outer and inner radius
+    pub fn star_polygon(
+        num_points: usize,
+        outer_radius: f32,
+        inner_radius: f32,
+        center: Pos2,
+    ) -> Self {
+        let angle = std::f32::consts::PI / num_points as f32;
+        let lines = (0..2 * num_points + 1)
+            .map(|i| {
+                let radius = if i % 2 == 0 {
+                    outer_radius
+                } else {
+                    inner_radius
+                };
+                let angle = i as f32 * angle;
+                center + radius * egui::vec2(angle.cos(), angle.sin())
+            })
+            .collect();
+
+        Self::new(vec![lines])
+    }
+
+    /// Closed polygon approximating an ellipse with the given semi-axes
+    pub fn ellipse(num_sides: usize, radii: Vec2, center: Pos2) -> Self {
+        let angle = 2. * std::f32::consts::PI / num_sides as f32;
+        let lines = (0..num_sides + 1)
+            .map(|i| {
+                let angle = i as f32 * angle;
+                center + radii * egui::vec2(angle.cos(), angle.sin())
+            })
+            .collect();
+
+        Self::new(vec![lines])
+    }
+
+    /// Cuts an opening of the given width centred on the segment starting at `point_idx`,
+    /// splitting the line in two.
+    pub fn cut_gap(&self, line_idx: usize, point_idx: usize, width: f32) -> Self {
+        let line = &self.lines[line_idx];
+        let (start, end) = (line[point_idx], line[point_idx + 1]);
+        let direction = (end - start).normalized();
+        let midpoint = start.lerp(end, 0.5);
+
+        let mut before = line[..=point_idx].to_vec();
+        before.push(midpoint - direction * width / 2.);
+
+        let mut after = vec![midpoint + direction * width / 2.];
+        after.extend_from_slice(&line[point_idx + 1..]);
+
+        let mut lines = self.lines.clone();
+        lines.splice(line_idx..=line_idx, [before, after]);
+
+        Self::new(lines)
     }
 
     pub fn all_segments(&self) -> Vec<Segment> {
@@ -42,6 +105,56 @@ impl Shape {
         segments
     }
 
+    /// Segments that close the gaps between consecutive lines, and between the last and first
+    pub fn openings(&self) -> Vec<Segment> {
+        let mut openings: Vec<Segment> = self
+            .lines
+            .windows(2)
+            .filter_map(|w| Some((*w[0].last()?, *w[1].first()?)))
+            .collect();
+
+        let first = self.lines.first().and_then(|line| line.first());
+        let last = self.lines.last().and_then(|line| line.last());
+        if let (Some(first), Some(last)) = (first, last) {
+            openings.push((*last, *first));
+        }
+
+        openings.retain(|(start, end)| start.distance(*end) > CLOSING_EPSILON);
+        openings
+    }
+
+    /// One shape per line, without the openings that join each line to its neighbours
+    pub fn split_into_components(&self) -> Vec<Shape> {
+        self.lines
+            .iter()
+            .map(|line| Shape::new(vec![line.clone()]))
+            .collect()
+    }
+
+    /// Whether any line is an open polyline, leaving a gap between its two ends
+    pub fn has_closing_gap(&self) -> bool {
+        self.lines
+            .iter()
+            .any(|line| match (line.first(), line.last()) {
+                (Some(first), Some(last)) => first.distance(*last) > CLOSING_EPSILON,
+                _ => false,
+            })
+    }
+
+    /// Rounds off corners with Chaikin's corner cutting, replacing each segment with points a
+    /// quarter and three quarters of the way along it. Closed lines stay closed, and open lines
+    /// keep their ends so that the gaps between them don't change.
+    pub fn smooth(&self, iterations: u32) -> Self {
+        let mut lines = self.lines.clone();
+        for _ in 0..iterations {
+            for line in &mut lines {
+                *line = cut_corners(line);
+            }
+        }
+
+        Self::new(lines)
+    }
+
     pub fn all_points(&self) -> Vec<Pos2> {
         self.lines
             .iter()
@@ -61,30 +174,43 @@ impl Shape {
         Rect::from_center_size(center_of_rotation, Vec2::splat(2. * max_radius))
     }
 
+    /// Distance from `center` to the nearest wall
+    pub fn inner_radius(&self, center: Pos2) -> f32 {
+        self.all_segments()
+            .into_iter()
+            .map(|segment| distance_to_segment(center, segment))
+            .fold(f32::INFINITY, f32::min)
+    }
+
     pub fn rotate(&self, angle: f32, center_of_rotation: Pos2) -> Self {
+        // Translating to the center and back isn't exact in floating point
+        if angle == 0.0 {
+            return self.clone();
+        }
+
+        let (sin, cos) = angle.sin_cos();
         let lines = self
             .lines
             .iter()
-            .map(|line| {
-                line.iter()
-                    .map(|p| {
-                        let p = *p - center_of_rotation;
-                        let p = egui::vec2(
-                            p.x * angle.cos() - p.y * angle.sin(),
-                            p.x * angle.sin() + p.y * angle.cos(),
-                        );
-                        center_of_rotation + p
-                    })
-                    .collect()
-            })
+            .map(|line| rotate_points(line, cos, sin, center_of_rotation))
             .collect();
 
-        Self { lines }
+        Self::new(lines)
+    }
+
+    pub fn translate(&self, offset: Vec2) -> Self {
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| line.iter().map(|p| *p + offset).collect())
+            .collect();
+
+        Self::new(lines)
     }
 }
 
-impl Drawable for Shape {
-    fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
+impl Shape {
+    pub fn draw_with_color(&self, painter: &egui::Painter, transform: TSTransform, color: Color32) {
         let lines = self
             .lines
             .iter()
@@ -95,13 +221,65 @@ impl Drawable for Shape {
             })
             .collect::<Vec<Line>>();
 
-        let stroke = egui::Stroke::new(1.0, ctx.style().visuals.text_color());
+        let stroke = egui::Stroke::new(1.0, color);
         for line in lines {
             painter.add(egui::Shape::line(line, stroke));
         }
     }
 }
 
+impl Drawable for Shape {
+    fn draw(&self, ctx: &egui::Context, painter: &egui::Painter, transform: TSTransform) {
+        self.draw_with_color(painter, transform, ctx.style().visuals.text_color());
+    }
+
+    fn draw_to_svg(&self) -> String {
+        let mut svg = String::new();
+        for line in self.lines.iter().filter(|line| !line.is_empty()) {
+            let mut commands = format!("M {} {}", line[0].x, line[0].y);
+            for point in &line[1..] {
+                commands += &format!(" L {} {}", point.x, point.y);
+            }
+            svg += &format!("<path d=\"{}\"/>\n", commands);
+        }
+        svg
+    }
+}
+
+fn cut_corners(line: &[Pos2]) -> Line {
+    let (Some(first), Some(last)) = (line.first(), line.last()) else {
+        return Vec::new();
+    };
+    if line.len() < 3 {
+        return line.to_vec();
+    }
+    let closed = first.distance(*last) <= CLOSING_EPSILON;
+
+    let mut smoothed: Line = line
+        .windows(2)
+        .flat_map(|w| [w[0].lerp(w[1], 0.25), w[0].lerp(w[1], 0.75)])
+        .collect();
+
+    if closed {
+        smoothed.push(smoothed[0]);
+    } else {
+        smoothed[0] = *first;
+        *smoothed.last_mut().unwrap() = *last;
+    }
+
+    smoothed
+}
+
+fn rotate_points(points: &[Pos2], cos: f32, sin: f32, center: Pos2) -> Line {
+    points
+        .iter()
+        .map(|point| {
+            let p = *point - center;
+            center + egui::vec2(p.x * cos - p.y * sin, p.x * sin + p.y * cos)
+        })
+        .collect()
+}
+
 pub fn compute_winding_number(point: Pos2, shape: &Shape) -> i32 {
     let mut winding_number = 0;
 
@@ -127,6 +305,29 @@ pub fn compute_winding_number(point: Pos2, shape: &Shape) -> i32 {
     winding_number
 }
 
+pub fn distance_to_segment(point: Pos2, segment: Segment) -> f32 {
+    let (start, end) = segment;
+    let v = end - start;
+    let length_sq = v.length_sq();
+    if length_sq == 0.0 {
+        return (point - start).length();
+    }
+
+    let t = ((point - start).dot(v) / length_sq).clamp(0.0, 1.0);
+    (point - (start + t * v)).length()
+}
+
+pub fn segments_intersect(a: Segment, b: Segment) -> bool {
+    let orientation = |p: Pos2, q: Pos2, r: Pos2| (q - p).x * (r - p).y - (q - p).y * (r - p).x;
+
+    let d1 = orientation(b.0, b.1, a.0);
+    let d2 = orientation(b.0, b.1, a.1);
+    let d3 = orientation(a.0, a.1, b.0);
+    let d4 = orientation(a.0, a.1, b.1);
+
+    (d1 > 0.0) != (d2 > 0.0) && (d3 > 0.0) != (d4 > 0.0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,4 +343,202 @@ mod tests {
         assert_eq!(compute_winding_number(Pos2::new(-4., 0.0), &shape), 0);
         assert_eq!(compute_winding_number(Pos2::new(-1., 1.), &shape), 0);
     }
+
+    #[test]
+    fn test_star_and_ellipse() {
+        let star = Shape::star_polygon(5, 1.0, 0.5, Pos2::ZERO);
+        assert_eq!(star.lines[0].len(), 11);
+        assert!(star.openings().is_empty());
+        assert!((star.lines[0][1].to_vec2().length() - 0.5).abs() < 1e-5);
+        assert_eq!(compute_winding_number(Pos2::ZERO, &star), 1);
+
+        let ellipse = Shape::ellipse(32, egui::vec2(1.0, 0.5), Pos2::ZERO);
+        assert!(ellipse.openings().is_empty());
+        let extent = ellipse.max_extent(Pos2::ZERO);
+        assert!((extent.width() - 2.0).abs() < 1e-5);
+        assert_eq!(compute_winding_number(Pos2::new(0.0, 0.4), &ellipse), 1);
+        assert_eq!(compute_winding_number(Pos2::new(0.0, 0.6), &ellipse), 0);
+    }
+
+    fn assert_close(a: &Shape, b: &Shape, tolerance: f32) {
+        for (p, q) in a.all_points().iter().zip(b.all_points()) {
+            assert!(
+                (p.x - q.x).abs() <= tolerance && (p.y - q.y).abs() <= tolerance,
+                "{p:?} differs from {q:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rotate_round_trips() {
+        let center = Pos2::new(0.3, -0.2);
+        let shape = Shape::star_polygon(5, 1.0, 0.4, Pos2::new(0.1, 0.1));
+
+        assert_close(&shape.rotate(std::f32::consts::TAU, center), &shape, 1e-4);
+        assert_close(
+            &shape.rotate(0.7, center).rotate(-0.7, center),
+            &shape,
+            1e-5,
+        );
+        assert_eq!(shape.rotate(0.0, center).lines, shape.lines);
+
+        let points = shape.rotate(1e6, center).all_points();
+        assert!(points.iter().all(|point| point.is_finite()));
+        assert!(points
+            .iter()
+            .zip(shape.all_points())
+            .all(|(p, q)| ((*p - center).length() - (q - center).length()).abs() < 1e-4));
+    }
+
+    #[test]
+    fn test_rotate_keeps_centroid() {
+        let center = Pos2::new(0.5, 0.25);
+        let shape = Shape::regular_polygon(6, 1.0, center);
+        let centroid = |shape: &Shape| {
+            // Skip the closing point, which repeats the first
+            let points = &shape.lines[0][..6];
+            points.iter().fold(Vec2::ZERO, |acc, p| acc + p.to_vec2()) / points.len() as f32
+        };
+
+        let rotated = shape.rotate(1.234, center);
+        assert!((centroid(&rotated) - centroid(&shape)).length() < 1e-5);
+        assert!((centroid(&rotated) - center.to_vec2()).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_inner_radius() {
+        let hexagon = Shape::regular_polygon(6, 1.0, Pos2::ZERO);
+        let apothem = (std::f32::consts::PI / 6.).cos();
+        assert!((hexagon.inner_radius(Pos2::ZERO) - apothem).abs() < 1e-5);
+        assert!((hexagon.inner_radius(Pos2::new(0.5, 0.0)) - apothem / 2.).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_components_and_closing_gaps() {
+        let hexagon = Shape::regular_polygon(6, 1.0, Pos2::ZERO);
+        assert!(!hexagon.has_closing_gap());
+        assert_eq!(hexagon.split_into_components(), vec![hexagon.clone()]);
+
+        let gapped = hexagon.cut_gap(0, 0, 0.2);
+        let components = gapped.split_into_components();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[1].lines[0], gapped.lines[1]);
+        assert!(gapped.has_closing_gap());
+
+        let open = Shape::new(vec![vec![
+            Pos2::ZERO,
+            Pos2::new(1.0, 0.0),
+            Pos2::new(1.0, 1.0),
+        ]]);
+        assert!(open.has_closing_gap());
+    }
+
+    #[test]
+    fn test_smooth() {
+        let square = Shape::regular_polygon(4, 1.0, Pos2::ZERO);
+        assert_eq!(square.smooth(0), square);
+
+        // Ratio of the furthest vertex to the nearest wall, which is 1 for a circle. The square's
+        // vertices are all equally far from the centre, so comparing vertices alone wouldn't
+        // show the corners being cut.
+        let roundness = |shape: &Shape| {
+            let furthest = shape
+                .all_points()
+                .iter()
+                .map(|p| p.distance(Pos2::ZERO))
+                .fold(f32::NEG_INFINITY, f32::max);
+            furthest / shape.inner_radius(Pos2::ZERO)
+        };
+
+        let smoothed = square.smooth(4);
+        assert_eq!(smoothed.lines[0].len(), 4 * 16 + 1);
+        assert!(!smoothed.has_closing_gap());
+        assert!(roundness(&square) > 1.4);
+        assert!(roundness(&smoothed) < 1.1);
+        assert!(roundness(&smoothed) < roundness(&square.smooth(1)));
+
+        // Open lines keep their ends, so gaps cut into a shape stay the same width
+        let gapped = square.cut_gap(0, 0, 0.2);
+        let smoothed = gapped.smooth(2);
+        assert_eq!(smoothed.openings(), gapped.openings());
+    }
+
+    #[test]
+    fn test_draw_to_svg() {
+        let square = Shape::regular_polygon(4, 1.0, Pos2::ZERO);
+        let svg = square.draw_to_svg();
+
+        assert_eq!(svg.matches("<path ").count(), 1);
+        assert_eq!(svg.matches('M').count(), 1);
+        assert_eq!(svg.matches('L').count(), 4);
+    }
+
+    #[test]
+    fn test_cut_gap() {
+        let shape = Shape::regular_polygon(4, 1.0, Pos2::ZERO).cut_gap(0, 0, 0.2);
+
+        assert_eq!(shape.lines.len(), 2);
+        assert_eq!(shape.lines[0].len(), 2);
+        assert_eq!(shape.lines[1].len(), 5);
+
+        let (gap_start, gap_end) = (shape.lines[0][1], shape.lines[1][0]);
+        assert!(((gap_end - gap_start).length() - 0.2).abs() < 1e-5);
+        assert_eq!(compute_winding_number(Pos2::ZERO, &shape), 1);
+    }
+
+    #[test]
+    fn test_openings() {
+        let closed = Shape::regular_polygon(4, 1.0, Pos2::ZERO);
+        assert!(closed.openings().is_empty());
+
+        let cut = closed.cut_gap(0, 1, 0.2);
+        let openings = cut.openings();
+        assert_eq!(openings.len(), 1);
+        assert_eq!(openings[0], (cut.lines[0][2], cut.lines[1][0]));
+    }
+
+    #[test]
+    fn test_draw_with_color() {
+        let shape = Shape::regular_polygon(4, 1.0, Pos2::ZERO);
+
+        let ctx = egui::Context::default();
+        let output = ctx.run(egui::RawInput::default(), |ctx| {
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            shape.draw_with_color(&painter, TSTransform::IDENTITY, Color32::RED);
+        });
+
+        let text_color = ctx.style().visuals.text_color();
+        let strokes: Vec<Color32> = output
+            .shapes
+            .iter()
+            .filter_map(|clipped| match &clipped.shape {
+                egui::Shape::Path(path) => match path.stroke.color {
+                    egui::epaint::ColorMode::Solid(colour) => Some(colour),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert!(!strokes.is_empty());
+        assert!(strokes.iter().all(|colour| *colour == Color32::RED));
+        assert!(!strokes.contains(&text_color));
+    }
+
+    #[test]
+    fn test_segments_intersect() {
+        let a = (Pos2::new(-1., 0.), Pos2::new(1., 0.));
+
+        assert!(segments_intersect(
+            a,
+            (Pos2::new(0., -1.), Pos2::new(0., 1.))
+        ));
+        assert!(!segments_intersect(
+            a,
+            (Pos2::new(0., 0.5), Pos2::new(0., 1.))
+        ));
+        assert!(!segments_intersect(
+            a,
+            (Pos2::new(2., -1.), Pos2::new(2., 1.))
+        ));
+    }
 }
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TutorialTrigger {
+    OnGameStart,
+    OnFirstCollision,
+    OnFirstBrakePress,
+    OnFirstBoostPress,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct TutorialStep {
+    pub trigger: TutorialTrigger,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TutorialState {
+    pub next_step: usize,
+    pub triggered: Vec<TutorialTrigger>,
+    /// Index of the step currently on screen and the tick it was shown at
+    pub active_step: Option<(usize, u64)>,
+}
+
+impl TutorialState {
+    pub fn notify(&mut self, trigger: TutorialTrigger) {
+        if !self.triggered.contains(&trigger) {
+            self.triggered.push(trigger);
+        }
+    }
+
+    /// Fires the pending steps, in order, whose triggers have occurred
+    pub fn advance(&mut self, steps: &[TutorialStep], tick: u64) {
+        while let Some(step) = steps.get(self.next_step) {
+            if !self.triggered.contains(&step.trigger) {
+                break;
+            }
+
+            self.active_step = Some((self.next_step, tick));
+            self.next_step += 1;
+        }
+    }
+
+    pub fn message<'a>(
+        &self,
+        steps: &'a [TutorialStep],
+        tick: u64,
+        display_ticks: u64,
+    ) -> Option<&'a str> {
+        let (index, shown_at) = self.active_step?;
+        if tick.saturating_sub(shown_at) >= display_ticks {
+            return None;
+        }
+
+        steps.get(index).map(|step| step.message.as_str())
+    }
+
+    pub fn dismiss(&mut self) {
+        self.active_step = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(trigger: TutorialTrigger) -> TutorialStep {
+        TutorialStep {
+            trigger,
+            message: format!("{trigger:?}"),
+        }
+    }
+
+    #[test]
+    fn test_steps_fire_in_order() {
+        let steps = vec![
+            step(TutorialTrigger::OnGameStart),
+            step(TutorialTrigger::OnFirstBrakePress),
+        ];
+        let mut state = TutorialState::default();
+
+        state.notify(TutorialTrigger::OnFirstBrakePress);
+        state.advance(&steps, 0);
+        assert_eq!(state.next_step, 0);
+        assert_eq!(state.message(&steps, 0, 10), None);
+
+        state.notify(TutorialTrigger::OnGameStart);
+        state.advance(&steps, 5);
+        assert_eq!(state.next_step, 2);
+        assert_eq!(state.message(&steps, 5, 10), Some("OnFirstBrakePress"));
+        assert_eq!(state.message(&steps, 15, 10), None);
+    }
+}
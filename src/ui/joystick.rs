@@ -0,0 +1,91 @@
+use egui::{Color32, Pos2, Rect, Sense, Vec2};
+
+/// Touch control for mobile: holding the left half brakes and holding the right half boosts.
+pub struct VirtualJoystick {
+    pub height: f32,
+    /// Fraction of the half-width around the centre that does nothing
+    pub dead_zone: f32,
+}
+
+impl Default for VirtualJoystick {
+    fn default() -> Self {
+        Self {
+            height: 120.0,
+            dead_zone: 0.15,
+        }
+    }
+}
+
+impl VirtualJoystick {
+    /// Returns whether brake and boost are held
+    pub fn show(&self, ui: &mut egui::Ui) -> (bool, bool) {
+        let size = Vec2::new(ui.available_width(), self.height);
+        let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+
+        let (brake, boost) = match response.interact_pointer_pos() {
+            Some(pos) if response.is_pointer_button_down_on() => zone_at(pos, rect, self.dead_zone),
+            _ => (false, false),
+        };
+
+        let painter = ui.painter_at(rect);
+        let radius = 0.5 * rect.width().min(rect.height());
+        let center = rect.center();
+
+        let half_fill = |colour: Color32, active: bool| {
+            let alpha = if active { 200 } else { 60 };
+            Color32::from_rgba_unmultiplied(colour.r(), colour.g(), colour.b(), alpha)
+        };
+        painter.add(egui::Shape::convex_polygon(
+            half_disc(center, radius, true),
+            half_fill(Color32::LIGHT_RED, brake),
+            egui::Stroke::NONE,
+        ));
+        painter.add(egui::Shape::convex_polygon(
+            half_disc(center, radius, false),
+            half_fill(Color32::LIGHT_GREEN, boost),
+            egui::Stroke::NONE,
+        ));
+        painter.circle_stroke(
+            center,
+            radius,
+            egui::Stroke::new(1.0, ui.visuals().text_color()),
+        );
+
+        (brake, boost)
+    }
+}
+
+/// Brake and boost state for a touch at `pos` within `rect`
+pub fn zone_at(pos: Pos2, rect: Rect, dead_zone: f32) -> (bool, bool) {
+    let offset = (pos.x - rect.center().x) / (0.5 * rect.width());
+    (offset < -dead_zone, offset > dead_zone)
+}
+
+fn half_disc(center: Pos2, radius: f32, left: bool) -> Vec<Pos2> {
+    let start = if left {
+        std::f32::consts::FRAC_PI_2
+    } else {
+        -std::f32::consts::FRAC_PI_2
+    };
+
+    (0..=32)
+        .map(|i| {
+            let angle = start + std::f32::consts::PI * i as f32 / 32.;
+            center + radius * Vec2::angled(angle)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_at() {
+        let rect = Rect::from_min_size(Pos2::ZERO, Vec2::new(200.0, 100.0));
+
+        assert_eq!(zone_at(Pos2::new(20.0, 50.0), rect, 0.15), (true, false));
+        assert_eq!(zone_at(Pos2::new(180.0, 50.0), rect, 0.15), (false, true));
+        assert_eq!(zone_at(Pos2::new(105.0, 50.0), rect, 0.15), (false, false));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod joystick;
+pub mod particles;
+pub mod timer;
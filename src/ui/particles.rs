@@ -0,0 +1,78 @@
+use egui::{emath::TSTransform, Color32, Pos2, Vec2};
+
+use crate::rng::XorShift;
+
+const BURST_COLOURS: [Color32; 4] = [
+    Color32::GOLD,
+    Color32::LIGHT_RED,
+    Color32::LIGHT_GREEN,
+    Color32::LIGHT_BLUE,
+];
+
+#[derive(Clone, Copy, Debug)]
+pub struct Particle {
+    pub pos: Pos2,
+    pub vel: Vec2,
+    pub color: Color32,
+    pub size: f32,
+    /// Seconds left before the particle disappears
+    pub life: f32,
+}
+
+/// Spawns `count` particles flying out from `origin` in the half circle facing `direction`
+pub fn burst(origin: Pos2, direction: Vec2, count: usize, seed: u64) -> Vec<Particle> {
+    let mut rng = XorShift::new(seed);
+    let base_angle = direction.angle();
+
+    (0..count)
+        .map(|i| {
+            let angle = base_angle + std::f32::consts::PI * (rng.next_f32() - 0.5);
+            let speed = 0.5 + 1.5 * rng.next_f32();
+            Particle {
+                pos: origin,
+                vel: speed * Vec2::angled(angle),
+                color: BURST_COLOURS[i % BURST_COLOURS.len()],
+                size: 0.01 + 0.02 * rng.next_f32(),
+                life: 1.5 + 0.5 * rng.next_f32(),
+            }
+        })
+        .collect()
+}
+
+/// Moves the particles along and removes the ones that have died
+pub fn update(particles: &mut Vec<Particle>, dt: f32) {
+    for particle in particles.iter_mut() {
+        particle.pos += particle.vel * dt;
+        particle.life -= dt;
+    }
+    particles.retain(|particle| particle.life > 0.0);
+}
+
+pub fn draw(particles: &[Particle], painter: &egui::Painter, transform: TSTransform) {
+    for particle in particles {
+        let opacity = particle.life.clamp(0.0, 1.0);
+        painter.circle_filled(
+            transform.mul_pos(particle.pos),
+            particle.size * transform.scaling,
+            particle.color.gamma_multiply(opacity),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_faces_direction_and_dies() {
+        let mut particles = burst(Pos2::ZERO, Vec2::X, 50, 7);
+        assert_eq!(particles.len(), 50);
+        assert!(particles.iter().all(|particle| particle.vel.x > -1e-6));
+
+        update(&mut particles, 1.0);
+        assert_eq!(particles.len(), 50);
+
+        update(&mut particles, 1.5);
+        assert!(particles.is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use egui::{Color32, Pos2, Response, Sense, Stroke, Ui, Vec2, Widget};
+
+/// A ring that depletes clockwise from the top as `progress` goes from 1 to 0.
+pub struct TimerRing {
+    progress: f32,
+    text: Option<String>,
+    diameter: f32,
+    thickness: f32,
+}
+
+impl TimerRing {
+    pub fn new(progress: f32) -> Self {
+        Self {
+            progress: progress.clamp(0.0, 1.0),
+            text: None,
+            diameter: 56.0,
+            thickness: 6.0,
+        }
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+}
+
+impl Widget for TimerRing {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(self.diameter), Sense::hover());
+        if !ui.is_rect_visible(rect) {
+            return response;
+        }
+
+        let painter = ui.painter();
+        let center = rect.center();
+        let radius = 0.5 * (self.diameter - self.thickness);
+
+        painter.circle_stroke(
+            center,
+            radius,
+            Stroke::new(self.thickness, ui.visuals().faint_bg_color),
+        );
+
+        let points = arc_points(center, radius, self.progress);
+        if points.len() > 1 {
+            painter.add(egui::Shape::line(
+                points,
+                Stroke::new(self.thickness, ring_colour(self.progress)),
+            ));
+        }
+
+        if let Some(text) = self.text {
+            painter.text(
+                center,
+                egui::Align2::CENTER_CENTER,
+                text,
+                egui::FontId::proportional(12.0),
+                ui.visuals().text_color(),
+            );
+        }
+
+        response
+    }
+}
+
+/// Green while more than half the time is left, fading through yellow to red after that
+pub fn ring_colour(progress: f32) -> Color32 {
+    let green = Color32::from_rgb(0, 200, 0);
+    let yellow = Color32::from_rgb(230, 200, 0);
+    let red = Color32::from_rgb(220, 40, 40);
+
+    if progress >= 0.5 {
+        green
+    } else if progress >= 0.33 {
+        lerp_colour(yellow, green, (progress - 0.33) / (0.5 - 0.33))
+    } else {
+        lerp_colour(red, yellow, progress / 0.33)
+    }
+}
+
+fn lerp_colour(a: Color32, b: Color32, t: f32) -> Color32 {
+    let channel = |a: u8, b: u8| egui::lerp(a as f32..=b as f32, t).round() as u8;
+    Color32::from_rgb(
+        channel(a.r(), b.r()),
+        channel(a.g(), b.g()),
+        channel(a.b(), b.b()),
+    )
+}
+
+/// Points along the arc that starts at the top and runs clockwise for `progress` of a turn
+fn arc_points(center: Pos2, radius: f32, progress: f32) -> Vec<Pos2> {
+    let segments = (64. * progress).ceil() as usize;
+    let start = -std::f32::consts::FRAC_PI_2;
+    let sweep = std::f32::consts::TAU * progress;
+
+    (0..=segments)
+        .map(|i| {
+            let angle = start + sweep * i as f32 / segments.max(1) as f32;
+            center + radius * Vec2::angled(angle)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_colour() {
+        assert_eq!(ring_colour(1.0), Color32::from_rgb(0, 200, 0));
+        assert_eq!(ring_colour(0.5), Color32::from_rgb(0, 200, 0));
+        assert_eq!(ring_colour(0.33), Color32::from_rgb(230, 200, 0));
+        assert_eq!(ring_colour(0.0), Color32::from_rgb(220, 40, 40));
+    }
+}
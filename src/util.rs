@@ -0,0 +1,56 @@
+/// Formats seconds since the Unix epoch as a YYYYMMDD_HHMMSS timestamp
+pub fn format_timestamp(secs: i64) -> String {
+    // Converts days since the epoch to a civil date, following
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = secs.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    let time = secs.rem_euclid(86400);
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Seconds to add to UTC to get local time. The browser knows the time zone; native builds have
+/// no time zone database to hand, so they stay on UTC.
+pub fn local_utc_offset_secs() -> i64 {
+    #[cfg(target_arch = "wasm32")]
+    return -(js_sys::Date::new_0().get_timezone_offset() as i64) * 60;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    return 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "19700101_000000");
+        assert_eq!(format_timestamp(-1), "19691231_235959");
+        assert_eq!(format_timestamp(946_684_799), "19991231_235959");
+        assert_eq!(format_timestamp(951_782_400), "20000229_000000");
+        assert_eq!(format_timestamp(1_709_210_096), "20240229_123456");
+        // 2100 is not a leap year
+        assert_eq!(format_timestamp(4_107_542_400), "21000301_000000");
+    }
+}